@@ -0,0 +1,108 @@
+//! Measures the throughput of pulling and returning elements from many threads at once, comparing
+//! search strategies, the free list, and sharding, with and without cache-line padding of the pool's bitset.
+//!
+//! Run with `cargo bench --bench contention`. The number of threads may be set with the
+//! `POOL_BENCH_THREADS` environment variable, and defaults to the available parallelism.
+//!
+//! Each configuration runs for one second, with every thread repeatedly pulling an element, incrementing it, and
+//! returning it. The pool holds 64 elements per thread. Padding can only make a difference when threads pull on
+//! separate cores at the same time, so padded and unpadded pools should be compared on a machine with several cores.
+
+use fixed_pool::*;
+use std::hint::*;
+use std::sync::atomic::*;
+use std::sync::*;
+use std::thread::*;
+use std::time::*;
+
+/// How long each configuration is measured for.
+const DURATION: Duration = Duration::from_millis(1000);
+
+/// The number of elements in the pool for each thread.
+const ELEMENTS_PER_THREAD: usize = 64;
+
+fn main() {
+    let threads = std::env::var("POOL_BENCH_THREADS")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or_else(|| available_parallelism().map(|x| x.get()).unwrap_or(4));
+
+    println!("{threads} threads, {ELEMENTS_PER_THREAD} elements per thread");
+    for search in [
+        SearchStrategy::LowestIndex,
+        SearchStrategy::RoundRobin,
+        SearchStrategy::PerThread,
+        SearchStrategy::Lifo,
+    ] {
+        for padded in [false, true] {
+            let builder = FixedPool::builder().search(search).padded(padded);
+            report(&format!("{search:?}"), padded, measure(threads, builder));
+        }
+    }
+
+    for padded in [false, true] {
+        let builder = FixedPool::builder()
+            .allocator(FreeListAllocator::new(threads * ELEMENTS_PER_THREAD))
+            .padded(padded);
+        report("FreeList", padded, measure(threads, builder));
+    }
+
+    for padded in [false, true] {
+        let pool = ShardedPool::from_shards((0..threads).map(|i| {
+            FixedPool::builder()
+                .elements((i * ELEMENTS_PER_THREAD) as u64..((i + 1) * ELEMENTS_PER_THREAD) as u64)
+                .padded(padded)
+                .build()
+        }));
+        report("Sharded", padded, run(threads, || pool.pull()));
+    }
+}
+
+/// Prints the throughput of a configuration.
+fn report(name: &str, padded: bool, throughput: f64) {
+    println!(
+        "{name:<12} padded: {padded:<5} {:>8.2} Mops/s",
+        throughput / 1e6
+    );
+}
+
+/// Pulls and returns elements of a pool from the given number of threads, returning the number of pulls per second.
+fn measure(threads: usize, builder: FixedPoolBuilder<u64>) -> f64 {
+    let pool = builder
+        .elements(0..(threads * ELEMENTS_PER_THREAD) as u64)
+        .build();
+    run(threads, || pool.pull())
+}
+
+/// Calls the given pull function from the given number of threads, returning the number of successful pulls per second.
+fn run(threads: usize, pull: impl Sync + Fn() -> Option<PoolBorrow<u64>>) -> f64 {
+    let start = Barrier::new(threads + 1);
+    let running = AtomicBool::new(true);
+    let pulls = AtomicUsize::new(0);
+
+    let started = scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                let mut count = 0;
+                start.wait();
+                while running.load(Ordering::Relaxed) {
+                    for _ in 0..64 {
+                        if let Some(mut borrow) = pull() {
+                            *borrow = black_box(*borrow + 1);
+                            count += 1;
+                        }
+                    }
+                }
+                pulls.fetch_add(count, Ordering::Relaxed);
+            });
+        }
+
+        start.wait();
+        let started = Instant::now();
+        sleep(DURATION);
+        running.store(false, Ordering::Relaxed);
+        started
+    });
+
+    pulls.load(Ordering::Relaxed) as f64 / started.elapsed().as_secs_f64()
+}
@@ -6,147 +6,4033 @@
 //! are automatically returned to the pool upon drop, and may have customized reset semantics through the use of a trait.
 
 use std::cell::*;
+use std::collections::*;
+use std::future::*;
+use std::hash::*;
 use std::marker::*;
+use std::mem::*;
 use std::ops::*;
+use std::panic::*;
+use std::pin::*;
 use std::sync::atomic::*;
 use std::sync::*;
+use std::task::*;
+use std::thread::{current, panicking, park, park_timeout, spawn, JoinHandle, Thread};
+use std::time::*;
 
 /// Allows for borrowing from a fixed pool of recycled values.
-pub struct FixedPool<T, R: Reset<T> = NoopReset>(Arc<FixedPoolInner<T>>, PhantomData<fn() -> R>);
+pub struct FixedPool<T, R: Reset<T> = NoopReset, V: Validate<T> = NoopValidate>(
+    Arc<FixedPoolInner<T>>,
+    PhantomData<fn() -> (R, V)>,
+);
 
-impl<T, R: Reset<T>> FixedPool<T, R> {
+impl<T, R: Reset<T>, V: Validate<T>> FixedPool<T, R, V> {
     /// Creates a pool which contains the given set of values.
     pub fn new(elements: impl IntoIterator<Item = T>) -> Self {
-        let elements = elements
-            .into_iter()
-            .map(UnsafeCell::new)
-            .collect::<Vec<_>>();
-        let pulled_element_len = elements.len().saturating_sub(1) / usize::BITS as usize + 1;
-        let mut pulled_elements = Vec::with_capacity(pulled_element_len);
+        Self::builder().elements(elements).build()
+    }
+
+    /// Creates a pool with room for `capacity` values, which are constructed by the factory the first time
+    /// that their slots are pulled and reused afterward.
+    pub fn with_factory(capacity: usize, factory: impl 'static + Fn() -> T + Send + Sync) -> Self {
+        Self::builder().max_size(capacity).factory(factory).build()
+    }
+
+    /// Creates a builder which can be used to configure a new pool.
+    pub fn builder() -> FixedPoolBuilder<T, R, V> {
+        FixedPoolBuilder::default()
+    }
 
-        for _ in 0..pulled_element_len {
-            pulled_elements.push(AtomicUsize::new(0));
+    /// Obtains a new value from the pool, or returns `None` if all elements are in use.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    pub fn pull(&self) -> Option<PoolBorrow<T, R, V>> {
+        let borrow = self.try_pull();
+        if borrow.is_none() {
+            self.exhausted();
         }
+        borrow
+    }
 
-        let remaining_elements_len = elements.len() % usize::BITS as usize;
-        if remaining_elements_len > 0 {
-            if let Some(last) = pulled_elements.last_mut() {
-                last.fetch_or(usize::MAX << remaining_elements_len, Ordering::AcqRel);
-            }
+    /// Obtains a new value from the pool without recording a failed pull if none is available.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    fn try_pull(&self) -> Option<PoolBorrow<T, R, V>> {
+        let index = self.acquire_valid()?;
+        Some(self.borrow(index, Caller::capture()))
+    }
+
+    /// Obtains a new value from the pool, or returns `None` if all elements are in use. Unlike [`FixedPool::pull`],
+    /// the borrow refers to the pool rather than holding a handle to it, which avoids the reference-counting
+    /// that pulling would otherwise incur.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    pub fn pull_scoped(&self) -> Option<ScopedBorrow<'_, T, R, V>> {
+        let Some(index) = self.acquire_valid() else {
+            self.exhausted();
+            return None;
+        };
+
+        self.lend(index, Caller::capture());
+        Some(ScopedBorrow { index, pool: self })
+    }
+
+    /// Obtains `count` values from the pool at once, or returns `None` without taking any if fewer than `count`
+    /// elements are available. Free elements which are stored next to each other are claimed together, which is
+    /// quicker than pulling them one at a time.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    pub fn pull_many(&self, count: usize) -> Option<Vec<PoolBorrow<T, R, V>>> {
+        let caller = Caller::capture();
+        if count > self.capacity() {
+            self.exhausted();
+            return None;
         }
 
-        Self(
-            Arc::new(FixedPoolInner {
-                pulled_elements,
-                elements,
-            }),
-            PhantomData,
+        let Some(indices) = self.acquire_many_valid(count) else {
+            self.exhausted();
+            return None;
+        };
+
+        Some(
+            indices
+                .into_iter()
+                .map(|index| self.borrow(index, caller))
+                .collect(),
         )
     }
 
-    /// Obtains a new value from the pool, or returns `None` if all elements are in use.
-    pub fn pull(&self) -> Option<PoolBorrow<T, R>> {
-        for (usize_index, value) in self.0.pulled_elements.iter().enumerate() {
-            let mut present_value = value.load(Ordering::Acquire);
-            let mut next_zero;
-            while {
-                next_zero = present_value.trailing_ones() as usize;
-                present_value |= !((usize::MAX << 1) << next_zero);
-                next_zero
-            } < usize::BITS as usize
-            {
-                let mask = 1 << next_zero;
-                if (value.fetch_or(mask, Ordering::AcqRel) & mask) == 0 {
-                    return Some(PoolBorrow {
-                        index: usize_index * usize::BITS as usize + next_zero,
-                        pool: self.clone(),
-                    });
+    /// Marks `count` free elements as in use, replacing any which fail validation, and returns their indices.
+    /// If fewer than `count` elements are available, then none are kept.
+    fn acquire_many_valid(&self, count: usize) -> Option<Vec<usize>> {
+        let mut batch = Batch {
+            inner: &self.0,
+            indices: Vec::with_capacity(count),
+        };
+
+        let mut position = 0;
+        while position < count {
+            if batch.indices.len() < count && !self.0.acquire_many(count, &mut batch.indices) {
+                return None;
+            }
+
+            // The element is removed from the batch while it is validated, since a failed validation poisons it.
+            let index = batch.indices.swap_remove(position);
+            unsafe {
+                if self.validate(index) {
+                    batch.indices.push(index);
+                    let last = batch.indices.len() - 1;
+                    batch.indices.swap(position, last);
+                    position += 1;
                 }
             }
         }
 
-        None
+        Some(take(&mut batch.indices))
+    }
+
+    /// Marks a free element as in use, skipping any which fail validation, and returns its index.
+    fn acquire_valid(&self) -> Option<usize> {
+        loop {
+            let index = self.0.acquire()?;
+            unsafe {
+                if self.validate(index) {
+                    return Some(index);
+                }
+            }
+        }
+    }
+
+    /// Obtains a new value from the pool, waiting asynchronously for one to be returned if all elements are in use.
+    /// The returned future is cancel-safe: dropping it never loses a wakeup that was meant for another waiter.
+    ///
+    /// In a pool without a [factory](FixedPoolBuilder::factory), vacant elements are never handed out, so the future
+    /// waits for an element which holds a value. If every element is vacant, such as after they have been discarded
+    /// when [reset](Reset::try_reset) or [evicted](FixedPool::evict_idle), then it waits forever. Such pools should
+    /// be pulled from with [`FixedPool::pull_or_create_async`] instead.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    pub fn pull_async(&self) -> PullFuture<'_, T, R, V> {
+        PullFuture {
+            pool: self,
+            waiter: None,
+            vacant: self.0.factory.is_some(),
+            caller: Caller::capture(),
+        }
+    }
+
+    /// Obtains a value from the pool, waiting asynchronously for one to be returned if all elements are in use.
+    /// Elements which already hold a value are preferred. If only vacant elements are free, then the value
+    /// produced by `create` is placed into one of them. If creation fails, the element stays vacant and
+    /// the error is returned. Like [`FixedPool::pull_async`], the returned future is cancel-safe.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    pub fn pull_or_create_async<E, F: Future<Output = Result<T, E>>, C: FnOnce() -> F>(
+        &self,
+        create: C,
+    ) -> impl Future<Output = Result<PoolBorrow<T, R, V>, E>> + use<'_, T, R, V, E, F, C> {
+        let caller = Caller::capture();
+        self.0.creates_async.store(true, Ordering::Relaxed);
+        async move {
+            let mut future = PullFuture {
+                pool: self,
+                waiter: None,
+                vacant: true,
+                caller,
+            };
+
+            let index = poll_fn(|cx| future.poll_acquire(cx)).await;
+            future.complete();
+            unsafe {
+                if self.0.is_occupied(index) && !self.check(index) {
+                    self.0.live.fetch_sub(1, Ordering::Relaxed);
+                    drop(self.0.take(index));
+                }
+            }
+
+            if !self.0.is_occupied(index) {
+                let vacancy = Vacancy {
+                    inner: &self.0,
+                    index,
+                };
+
+                let value = create().await?;
+                forget(vacancy);
+                unsafe {
+                    self.0.insert(index, value);
+                }
+            }
+
+            Ok(self.borrow(index, caller))
+        }
+    }
+
+    /// Obtains a new value from the pool, blocking the current thread until one is returned if all elements are in use.
+    /// Like [`FixedPool::pull_async`], this blocks forever if the pool has no factory and every element is vacant.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    pub fn pull_blocking(&self) -> PoolBorrow<T, R, V> {
+        self.pull_until(None)
+            .expect("Pull without a deadline should never time out.")
+    }
+
+    /// Obtains a new value from the pool, blocking the current thread until one is returned if all elements are in use.
+    /// Returns `None` if no value became available before the timeout elapsed.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    pub fn pull_timeout(&self, timeout: Duration) -> Option<PoolBorrow<T, R, V>> {
+        let borrow = self.pull_until(Instant::now().checked_add(timeout));
+        if borrow.is_none() {
+            self.exhausted();
+        }
+        borrow
+    }
+
+    /// Blocks the current thread until a value is available or the deadline, if any, has passed.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    fn pull_until(&self, deadline: Option<Instant>) -> Option<PoolBorrow<T, R, V>> {
+        if let Some(borrow) = self.try_pull() {
+            return Some(borrow);
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = self.pull_async();
+
+        loop {
+            if let Poll::Ready(borrow) = Pin::new(&mut future).poll(&mut cx) {
+                return Some(borrow);
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if deadline <= now {
+                        return None;
+                    }
+                    park_timeout(deadline - now);
+                }
+                None => park(),
+            }
+        }
+    }
+
+    /// The name given to the pool by its builder, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.0.name.as_deref()
+    }
+
+    /// The maximum number of elements which the pool can hold.
+    pub fn capacity(&self) -> usize {
+        self.0.elements.capacity
+    }
+
+    /// The number of elements which are neither borrowed nor poisoned. This includes vacant elements
+    /// that have yet to be created, but only if the pool has a factory with which to create them.
+    pub fn available(&self) -> usize {
+        self.0.available()
+    }
+
+    /// The number of elements which are currently borrowed.
+    pub fn in_use(&self) -> usize {
+        self.0.in_use()
+    }
+
+    /// Whether the element at the given index is currently borrowed.
+    pub fn is_borrowed(&self, index: usize) -> bool {
+        assert!(index < self.capacity(), "Index was out of bounds for pool.");
+        self.0.pulled_elements.contains(index, Ordering::Acquire)
+            && !self.0.is_poisoned(index)
+            && !self.0.cached_elements.contains(index, Ordering::Acquire)
+    }
+
+    /// Takes a snapshot of the statistics which the pool has recorded since its creation.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> PoolStats {
+        self.0.stats.snapshot()
+    }
+
+    /// Reports every borrow which has been held for at least the given duration, along with where it was pulled.
+    /// This is useful for finding borrows which were stored somewhere and never dropped.
+    #[cfg(feature = "leak-detection")]
+    pub fn outstanding_borrows(&self, threshold: Duration) -> Vec<OutstandingBorrow> {
+        let now = Instant::now();
+        self.0
+            .elements
+            .allocated()
+            .filter_map(|index| {
+                let site = unsafe { self.0.elements.get_unchecked(index) }
+                    .borrow_site
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone()?;
+                let held_for = now.saturating_duration_since(site.pulled_at);
+                (held_for >= threshold).then(|| OutstandingBorrow {
+                    index,
+                    held_for,
+                    location: site.location,
+                    backtrace: site.backtrace,
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the element at the given index has been poisoned and withheld from the pool. Elements are poisoned
+    /// when [`Reset::try_reset`] returns [`ResetError::Poison`], when resetting or validating them panics, or when
+    /// they fail validation in a pool without a factory.
+    pub fn is_poisoned(&self, index: usize) -> bool {
+        assert!(
+            index < self.0.elements.capacity,
+            "Index was out of bounds for pool."
+        );
+        self.0.is_poisoned(index)
+    }
+
+    /// Gets the indices of all elements which are currently poisoned.
+    pub fn poisoned(&self) -> impl '_ + Iterator<Item = usize> {
+        self.0.poisoned_elements.ones(Ordering::Acquire)
+    }
+
+    /// Adds the pool to the process-wide registry, so that it is included in [`registered_pools`]. The pool remains
+    /// registered until every handle to it has been dropped. Registering a pool more than once has no effect.
+    pub fn register(&self)
+    where
+        T: 'static + Send + Sync,
+    {
+        self.0.registration.get_or_init(|| {
+            let id = NEXT_REGISTRATION.fetch_add(1, Ordering::Relaxed);
+            let inner: Weak<dyn Diagnose + Send + Sync> = Arc::downgrade(&self.0) as _;
+            REGISTRY
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(id, inner);
+            id
+        });
+    }
+
+    /// Repairs a poisoned element by replacing its value, and returns the element to the pool.
+    /// Returns the value which the element held before, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element at the given index is not poisoned.
+    pub fn clear_poison(&self, index: usize, value: T) -> Option<T> {
+        assert!(
+            index < self.0.elements.capacity,
+            "Index was out of bounds for pool."
+        );
+        assert!(
+            self.0.poisoned_elements.remove(index, Ordering::AcqRel),
+            "Element was not poisoned."
+        );
+
+        unsafe {
+            let previous = self.0.take(index);
+            if previous.is_some() {
+                self.0.live.fetch_sub(1, Ordering::Relaxed);
+            }
+
+            self.0.insert(index, value);
+            self.0.release(index);
+            previous
+        }
+    }
+
+    /// Drops elements which have not been borrowed for longer than the pool's [idle timeout](FixedPoolBuilder::idle_timeout),
+    /// without shrinking the pool below its [minimum size](FixedPoolBuilder::min_size). Dropped elements become vacant,
+    /// and are recreated when they are next needed. Pools without a [factory](FixedPoolBuilder::factory) only drop
+    /// elements once they have been pulled from with [`FixedPool::pull_or_create_async`], since other pulls could not
+    /// recreate them. Returns the number of elements which were dropped.
+    pub fn evict_idle(&self) -> usize {
+        self.0.evict_idle()
+    }
+
+    /// Spawns a background thread which calls [`FixedPool::evict_idle`] at the given interval.
+    /// The thread is woken and exits as soon as every handle to the pool has been dropped.
+    pub fn spawn_evictor(&self, interval: Duration) -> JoinHandle<()>
+    where
+        T: 'static + Send + Sync,
+    {
+        let inner = Arc::downgrade(&self.0);
+        let handle = spawn(move || {
+            let mut next = Instant::now() + interval;
+            loop {
+                park_timeout(next.saturating_duration_since(Instant::now()));
+                let Some(inner) = inner.upgrade() else {
+                    break;
+                };
+
+                if next <= Instant::now() {
+                    inner.evict_idle();
+                    next = Instant::now() + interval;
+                }
+            }
+        });
+
+        self.0
+            .evictors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(handle.thread().clone());
+        handle
+    }
+
+    /// Checks whether the pulled element at the given index may be handed out. An element which fails validation
+    /// is dropped and recreated by the factory if there is one; otherwise, it is poisoned with its value left in
+    /// place, and `false` is returned.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller, and must hold a value.
+    unsafe fn validate(&self, index: usize) -> bool {
+        if self.check(index) {
+            return true;
+        }
+
+        if self.0.factory.is_some() {
+            self.0.live.fetch_sub(1, Ordering::Relaxed);
+            drop(self.0.take(index));
+            self.0.fill(index);
+            true
+        } else {
+            self.0.poison(index);
+            false
+        }
+    }
+
+    /// Runs the validation policy on the pulled element at the given index. If validation panics,
+    /// the element is poisoned before the panic is propagated.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller, and must hold a value.
+    unsafe fn check(&self, index: usize) -> bool {
+        match catch_unwind(AssertUnwindSafe(|| V::validate(&mut *self.0.value(index)))) {
+            Ok(valid) => valid,
+            Err(payload) => {
+                self.0.poison(index);
+                resume_unwind(payload);
+            }
+        }
+    }
+
+    /// Records that a pull failed because no element was available.
+    fn exhausted(&self) {
+        #[cfg(feature = "stats")]
+        self.0.stats.record_failure();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(pool = self.name(), "pool exhausted");
+    }
+
+    /// Wraps the acquired element at the given index in a borrow, which was pulled from the given location.
+    fn borrow(&self, index: usize, caller: Caller) -> PoolBorrow<T, R, V> {
+        self.lend(index, caller);
+        PoolBorrow {
+            index,
+            pool: self.clone(),
+        }
+    }
+
+    /// Records that the acquired element at the given index is being lent out from the given location.
+    fn lend(&self, index: usize, caller: Caller) {
+        #[cfg(feature = "leak-detection")]
+        self.0.track(index, caller);
+        #[cfg(not(feature = "leak-detection"))]
+        let _ = (index, caller);
+        #[cfg(feature = "stats")]
+        unsafe {
+            self.0.stats.record_pull();
+            *self.0.elements.get_unchecked(index).pulled_at.get() = Instant::now();
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(pool = self.name(), index, "borrowed pool element");
+    }
+
+    /// Resets the borrowed element at the given index and returns it to the pool. If the element cannot be reset,
+    /// it is discarded or poisoned instead.
+    ///
+    /// # Safety
+    ///
+    /// The element must have been lent out, and must not be accessed by the borrower afterward.
+    unsafe fn restore(&self, index: usize) {
+        #[cfg(feature = "stats")]
+        self.0
+            .stats
+            .record_return((*self.0.elements.get_unchecked(index).pulled_at.get()).elapsed());
+        #[cfg(feature = "leak-detection")]
+        self.0.untrack(index);
+
+        let result = catch_unwind(AssertUnwindSafe(|| R::try_reset(&mut *self.0.value(index))));
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            pool = self.name(),
+            index,
+            outcome = match &result {
+                Ok(Ok(())) => "returned",
+                Ok(Err(ResetError::Discard)) => "discarded",
+                Ok(Err(ResetError::Poison)) => "poisoned",
+                Err(_) => "panicked",
+            },
+            "reset pool element"
+        );
+
+        let discarded = match result {
+            Ok(Ok(())) => {
+                self.0.mark_returned(index);
+                if FixedPoolInner::cache(&self.0, index) {
+                    return;
+                }
+                None
+            }
+            Ok(Err(ResetError::Discard)) => {
+                self.0.live.fetch_sub(1, Ordering::Relaxed);
+                self.0.take(index)
+            }
+            Ok(Err(ResetError::Poison)) => {
+                self.0.poison(index);
+                return;
+            }
+            Err(payload) => {
+                self.0.poison(index);
+                if !panicking() {
+                    resume_unwind(payload);
+                }
+                return;
+            }
+        };
+
+        self.0.release_returned(index);
+        drop(discarded);
+    }
+}
+
+/// Configures and creates a fixed pool.
+pub struct FixedPoolBuilder<T, R: Reset<T> = NoopReset, V: Validate<T> = NoopValidate> {
+    /// The elements which the pool will initially contain.
+    elements: Vec<T>,
+    /// The maximum number of elements which the pool may grow to hold.
+    max_size: Option<usize>,
+    /// The function used to create new elements as the pool grows.
+    factory: Option<Box<dyn Fn() -> T + Send + Sync>>,
+    /// How long an element may go unborrowed before it is eligible for eviction.
+    idle_timeout: Option<Duration>,
+    /// The number of elements below which eviction will not shrink the pool.
+    min_size: usize,
+    /// Whether returned elements should be handed directly to waiting tasks.
+    fair: bool,
+    /// How pulls choose which free element to take.
+    search: SearchStrategy,
+    /// Whether each word of the bitset of borrowed elements occupies its own cache line.
+    padded: bool,
+    /// The number of returned elements which each thread may keep for its own next pulls.
+    thread_cache: usize,
+    /// The allocator which chooses the free elements that pulls take, if not the default bitset search.
+    allocator: Option<Box<dyn SlotAllocator>>,
+    /// The name used to identify the pool in diagnostics.
+    name: Option<String>,
+    /// Marker for the reset type.
+    marker: PhantomData<fn() -> (R, V)>,
+}
+
+impl<T, R: Reset<T>, V: Validate<T>> FixedPoolBuilder<T, R, V> {
+    /// Adds the given set of values to the pool.
+    pub fn elements(mut self, elements: impl IntoIterator<Item = T>) -> Self {
+        self.elements.extend(elements);
+        self
+    }
+
+    /// Sets the maximum number of elements which the pool may hold. If this exceeds the number of initial
+    /// elements, then the remaining elements start out vacant. Vacant elements are created by the
+    /// [`factory`](Self::factory) whenever all existing elements are in use, or by
+    /// [`FixedPool::pull_or_create_async`]. Defaults to the number of initial elements.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Sets the function used to create new elements when the pool grows.
+    pub fn factory(mut self, factory: impl 'static + Fn() -> T + Send + Sync) -> Self {
+        self.factory = Some(Box::new(factory));
+        self
+    }
+
+    /// Sets how long an element may go unborrowed before [`FixedPool::evict_idle`] drops it.
+    /// Evicted elements become vacant, and are recreated in the same way as other vacant elements.
+    /// Eviction only takes place in pools with a [factory](Self::factory), or in pools which have been
+    /// pulled from with [`FixedPool::pull_or_create_async`]. In the latter case, only that method
+    /// recreates evicted elements, while other pulls pass over them.
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Sets the number of elements below which eviction will not shrink the pool. Defaults to zero.
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Sets whether the pool hands out elements fairly. When enabled, an element which is returned while
+    /// tasks or threads are waiting in [`FixedPool::pull_async`] or [`FixedPool::pull_blocking`] is given
+    /// directly to the longest-waiting one, rather than being freed for any caller to take. Disabled by default.
+    pub fn fair(mut self, fair: bool) -> Self {
+        self.fair = fair;
+        self
+    }
+
+    /// Sets how pulls choose which free element to take. Spreading pulls across the pool reduces contention
+    /// when many threads pull at once, while reusing recently returned elements keeps their memory in cache.
+    /// [`SearchStrategy::Lifo`] applies with any [allocator](Self::allocator), while the other strategies only
+    /// configure the default [`BitsetAllocator`]. Defaults to [`SearchStrategy::LowestIndex`].
+    pub fn search(mut self, search: SearchStrategy) -> Self {
+        self.search = search;
+        self
+    }
+
+    /// Sets whether each word of the bitset which tracks borrowed elements is placed on its own cache line. This
+    /// prevents threads which pull from different parts of the pool from contending over the same cache line, at the
+    /// cost of some memory. It is most effective alongside a [search strategy](FixedPoolBuilder::search) which spreads
+    /// pulls across the pool. Disabled by default.
+    pub fn padded(mut self, padded: bool) -> Self {
+        self.padded = padded;
+        self
+    }
+
+    /// Sets how many returned elements each thread may keep for itself. A thread which returns an element and
+    /// then pulls again receives the same element, without searching the pool. Cached elements are still taken
+    /// by other threads when nothing else is free, are never kept while tasks are waiting for an element, and are
+    /// returned to the pool once the thread exits. Disabled by default.
+    pub fn thread_cache(mut self, limit: usize) -> Self {
+        self.thread_cache = limit;
+        self
+    }
+
+    /// Sets the allocator which chooses the free elements that pulls take. The allocator's length must equal the
+    /// pool's capacity. By default, pools use a [`BitsetAllocator`] with the pool's [search strategy](Self::search),
+    /// while a [`FreeListAllocator`] takes constant time to pull and return elements in pools with many thousands
+    /// of them. Resets, validation, thread caching, and the other features of the pool work as usual on top of
+    /// the allocator.
+    pub fn allocator(mut self, allocator: impl 'static + SlotAllocator) -> Self {
+        self.allocator = Some(Box::new(allocator));
+        self
+    }
+
+    /// Sets the name used to identify the pool in diagnostics.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Creates the pool.
+    pub fn build(self) -> FixedPool<T, R, V> {
+        let capacity = self.max_size.unwrap_or(self.elements.len());
+        assert!(
+            self.elements.len() <= capacity,
+            "Pool was given more initial elements than its maximum size."
+        );
+
+        let pulled_elements = Bitset::slots(capacity, self.padded);
+        let pulled_element_len = pulled_elements.len();
+
+        let occupied_elements = Bitset::new(pulled_element_len, false, |i| {
+            let occupied_len = self
+                .elements
+                .len()
+                .saturating_sub(i * usize::BITS as usize)
+                .min(usize::BITS as usize);
+            usize::MAX
+                .checked_shr(usize::BITS - occupied_len as u32)
+                .unwrap_or(0)
+        });
+
+        let allocator = self
+            .allocator
+            .unwrap_or_else(|| Box::new(BitsetAllocator::new(capacity, self.search)));
+        assert_eq!(
+            allocator.len(),
+            capacity,
+            "Slot allocator length did not match pool capacity."
+        );
+
+        let inner = FixedPoolInner {
+            pulled_elements,
+            occupied_elements,
+            poisoned_elements: Bitset::new(pulled_element_len, false, |_| 0),
+            live: AtomicUsize::new(self.elements.len()),
+            elements: Segments::new(self.elements, capacity),
+            factory: self.factory,
+            creates_async: AtomicBool::new(false),
+            idle_timeout: self.idle_timeout,
+            min_size: self.min_size,
+            fair: self.fair,
+            search: self.search,
+            thread_cache: self.thread_cache,
+            cached_elements: Bitset::new(pulled_element_len, self.padded, |_| 0),
+            allocator,
+            recent: Mutex::default(),
+            name: self.name,
+            waiters: Mutex::default(),
+            waiting: AtomicUsize::new(0),
+            #[cfg(feature = "stats")]
+            stats: PoolCounters::default(),
+            registration: OnceLock::new(),
+            evictors: Mutex::default(),
+        };
+
+        let slots = inner.slots();
+        for index in (0..capacity).rev() {
+            inner.allocator.release(&slots, index);
+        }
+
+        FixedPool(Arc::new(inner), PhantomData)
+    }
+}
+
+impl<T, R: Reset<T>, V: Validate<T>> Default for FixedPoolBuilder<T, R, V> {
+    fn default() -> Self {
+        Self {
+            elements: Vec::new(),
+            max_size: None,
+            factory: None,
+            idle_timeout: None,
+            min_size: 0,
+            fair: false,
+            search: SearchStrategy::LowestIndex,
+            padded: false,
+            thread_cache: 0,
+            allocator: None,
+            name: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: std::fmt::Debug, R: Reset<T>, V: Validate<T>> std::fmt::Debug
+    for FixedPoolBuilder<T, R, V>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FixedPoolBuilder")
+            .field("elements", &self.elements)
+            .field("max_size", &self.max_size)
+            .field("factory", &self.factory.is_some())
+            .field("idle_timeout", &self.idle_timeout)
+            .field("min_size", &self.min_size)
+            .field("fair", &self.fair)
+            .field("search", &self.search)
+            .field("padded", &self.padded)
+            .field("thread_cache", &self.thread_cache)
+            .field("allocator", &self.allocator.is_some())
+            .field("name", &self.name)
+            .finish()
     }
 }
 
-impl<T, R: Reset<T>> Clone for FixedPool<T, R> {
+impl<T, R: Reset<T>, V: Validate<T>> Clone for FixedPool<T, R, V> {
     fn clone(&self) -> Self {
         Self(self.0.clone(), PhantomData)
     }
 }
 
-impl<T: std::fmt::Debug, R: Reset<T>> std::fmt::Debug for FixedPool<T, R> {
+impl<T: std::fmt::Debug, R: Reset<T>, V: Validate<T>> std::fmt::Debug for FixedPool<T, R, V> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("FixedPool").finish()
+        f.debug_struct("FixedPool")
+            .field("name", &self.name())
+            .field("capacity", &self.capacity())
+            .field("available", &self.available())
+            .field("in_use", &self.in_use())
+            .field("occupancy", &Occupancy(self))
+            .finish()
     }
 }
 
-/// Holds the inner state for a fixed pool.
-struct FixedPoolInner<T> {
-    /// A bitset representing the elements which are presently in use.
-    pub pulled_elements: Vec<AtomicUsize>,
-    /// The set of elements.
-    pub elements: Vec<UnsafeCell<T>>,
-}
+/// The number of elements beyond which the occupancy map of a pool's debug output is cut short.
+const OCCUPANCY_LIMIT: usize = 256;
 
-unsafe impl<T: Send> Send for FixedPoolInner<T> {}
-unsafe impl<T: Sync> Sync for FixedPoolInner<T> {}
+/// Formats a map of the pool's elements, with one character per element: `#` for borrowed
+/// elements, `!` for poisoned elements, `_` for vacant elements which cannot be pulled because
+/// the pool has no factory, and `.` for available elements. Only the first [`OCCUPANCY_LIMIT`]
+/// elements are mapped, followed by a count of the rest.
+struct Occupancy<'a, T, R: Reset<T>, V: Validate<T>>(&'a FixedPool<T, R, V>);
 
-/// Represents an object which is borrowed from the fixed pool.
-#[derive(Debug)]
-pub struct PoolBorrow<T, R: Reset<T> = NoopReset> {
-    /// The index in the pool of the borrowed element.
-    index: usize,
-    /// The pool itself.
-    pool: FixedPool<T, R>,
-}
+impl<'a, T, R: Reset<T>, V: Validate<T>> std::fmt::Debug for Occupancy<'a, T, R, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use std::fmt::Write;
 
-impl<T, R: Reset<T>> PoolBorrow<T, R> {
-    /// The index of the item within the pool.
-    pub fn index(&self) -> usize {
-        self.index
+        let FixedPool(inner, _) = self.0;
+        let mapped = self.0.capacity().min(OCCUPANCY_LIMIT);
+        f.write_char('"')?;
+        for index in 0..mapped {
+            f.write_char(if self.0.is_poisoned(index) {
+                '!'
+            } else if self.0.is_borrowed(index) {
+                '#'
+            } else if inner.factory.is_none() && !inner.is_occupied(index) {
+                '_'
+            } else {
+                '.'
+            })?;
+        }
+        f.write_char('"')?;
+
+        if mapped < self.0.capacity() {
+            write!(f, " and {} more", self.0.capacity() - mapped)?;
+        }
+        Ok(())
     }
 }
 
-impl<T, R: Reset<T>> Deref for PoolBorrow<T, R> {
-    type Target = T;
+/// Splits elements across several fixed pools, so that threads which pull at once contend over separate bitsets.
+/// Each thread pulls from its own shard first, and takes elements from the other shards only when its own is
+/// exhausted. Borrows are ordinary [`PoolBorrow`]s, which return to the shard that they came from.
+pub struct ShardedPool<T, R: Reset<T> = NoopReset, V: Validate<T> = NoopValidate> {
+    /// The pools which hold the elements.
+    shards: Arc<[FixedPool<T, R, V>]>,
+}
 
-    fn deref(&self) -> &Self::Target {
-        unsafe { &*(self.pool.0.elements.get_unchecked(self.index).get() as *const _) }
+impl<T, R: Reset<T>, V: Validate<T>> ShardedPool<T, R, V> {
+    /// Creates a pool with the given number of shards, across which the given values are dealt evenly.
+    pub fn new(shards: usize, elements: impl IntoIterator<Item = T>) -> Self {
+        assert!(shards > 0, "Sharded pool must have at least one shard.");
+        let mut shard_elements = (0..shards).map(|_| Vec::new()).collect::<Vec<_>>();
+        for (i, element) in elements.into_iter().enumerate() {
+            shard_elements[i % shards].push(element);
+        }
+
+        Self::from_shards(shard_elements.into_iter().map(FixedPool::new))
     }
-}
 
-impl<T, R: Reset<T>> DerefMut for PoolBorrow<T, R> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe { &mut *self.pool.0.elements.get_unchecked(self.index).get() }
+    /// Creates a pool from the given shards, which may each be configured with their own [builder](FixedPool::builder).
+    pub fn from_shards(shards: impl IntoIterator<Item = FixedPool<T, R, V>>) -> Self {
+        let shards = shards.into_iter().collect::<Arc<[_]>>();
+        assert!(
+            !shards.is_empty(),
+            "Sharded pool must have at least one shard."
+        );
+        Self { shards }
+    }
+
+    /// Obtains a new value from the current thread's shard, or from another shard if that one is exhausted.
+    /// Returns `None` if all elements are in use.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    pub fn pull(&self) -> Option<PoolBorrow<T, R, V>> {
+        let (shard, index) = self.acquire()?;
+        Some(shard.borrow(index, Caller::capture()))
+    }
+
+    /// Obtains a new value from the current thread's shard, or from another shard if that one is exhausted.
+    /// Returns `None` if all elements are in use. Like [`FixedPool::pull_scoped`], the borrow refers to its
+    /// shard rather than holding a handle to it.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    pub fn pull_scoped(&self) -> Option<ScopedBorrow<'_, T, R, V>> {
+        let (shard, index) = self.acquire()?;
+        shard.lend(index, Caller::capture());
+        Some(ScopedBorrow { index, pool: shard })
+    }
+
+    /// The pools which hold the elements.
+    pub fn shards(&self) -> &[FixedPool<T, R, V>] {
+        &self.shards
+    }
+
+    /// The index of the shard from which the current thread pulls first.
+    pub fn local_shard(&self) -> usize {
+        SHARD_HINT.with(|hint| *hint) % self.shards.len()
+    }
+
+    /// The maximum number of elements which the shards can hold in total.
+    pub fn capacity(&self) -> usize {
+        self.shards.iter().map(FixedPool::capacity).sum()
+    }
+
+    /// The number of elements across all shards which are neither borrowed nor poisoned, excluding vacant
+    /// elements in shards which have no factory.
+    pub fn available(&self) -> usize {
+        self.shards.iter().map(FixedPool::available).sum()
+    }
+
+    /// The number of elements across all shards which are currently borrowed.
+    pub fn in_use(&self) -> usize {
+        self.shards.iter().map(FixedPool::in_use).sum()
+    }
+
+    /// Marks a free element as in use, searching the current thread's shard before the others in turn,
+    /// and returns the shard along with the element's index.
+    fn acquire(&self) -> Option<(&FixedPool<T, R, V>, usize)> {
+        let local = self.local_shard();
+        for i in 0..self.shards.len() {
+            let shard = &self.shards[(local + i) % self.shards.len()];
+            if let Some(index) = shard.acquire_valid() {
+                return Some((shard, index));
+            }
+        }
+
+        self.shards[local].exhausted();
+        None
     }
 }
 
-impl<T, R: Reset<T>> Drop for PoolBorrow<T, R> {
-    fn drop(&mut self) {
-        unsafe {
-            R::reset(&mut *self);
-            let usize_index = self.index / usize::BITS as usize;
-            let bit_index = self.index % usize::BITS as usize;
-            self.pool
-                .0
-                .pulled_elements
-                .get_unchecked(usize_index)
-                .fetch_and(!(1 << bit_index), Ordering::AcqRel);
+impl<T, R: Reset<T>, V: Validate<T>> Clone for ShardedPool<T, R, V> {
+    fn clone(&self) -> Self {
+        Self {
+            shards: self.shards.clone(),
         }
     }
 }
 
-/// Determines how an object is reset when it is returned to the pool.
-pub trait Reset<T> {
-    /// Resets the provided value.
-    fn reset(value: &mut T);
+impl<T: std::fmt::Debug, R: Reset<T>, V: Validate<T>> std::fmt::Debug for ShardedPool<T, R, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShardedPool")
+            .field("capacity", &self.capacity())
+            .field("available", &self.available())
+            .field("in_use", &self.in_use())
+            .field("shards", &self.shards)
+            .finish()
+    }
 }
 
-/// Does nothing when resetting an object.
-#[derive(Copy, Clone, Debug)]
-pub struct NoopReset;
+/// Holds the inner state for a fixed pool.
+struct FixedPoolInner<T> {
+    /// A bitset representing the elements which are presently in use.
+    pub pulled_elements: Bitset,
+    /// A bitset representing the elements which hold a value. Bits may only be changed by whoever has pulled the element.
+    pub occupied_elements: Bitset,
+    /// A bitset representing the elements which have been poisoned. Poisoned elements remain marked as pulled.
+    pub poisoned_elements: Bitset,
+    /// The set of elements.
+    pub elements: Segments<T>,
+    /// The number of elements which currently hold a value.
+    pub live: AtomicUsize,
+    /// The function used to create new elements as the pool grows.
+    pub factory: Option<Box<dyn Fn() -> T + Send + Sync>>,
+    /// Whether [`FixedPool::pull_or_create_async`] has been used, so that vacant elements may be recreated even
+    /// without a factory.
+    pub creates_async: AtomicBool,
+    /// How long an element may go unborrowed before it is eligible for eviction.
+    pub idle_timeout: Option<Duration>,
+    /// The number of elements below which eviction will not shrink the pool.
+    pub min_size: usize,
+    /// Whether returned elements are handed directly to the longest-waiting task.
+    pub fair: bool,
+    /// How pulls choose which free element to take.
+    pub search: SearchStrategy,
+    /// The number of returned elements which each thread may keep for its own next pulls.
+    pub thread_cache: usize,
+    /// A bitset representing the elements which are held in a thread's cache. Cached elements remain marked as pulled.
+    pub cached_elements: Bitset,
+    /// The allocator which chooses the free elements that pulls take.
+    pub allocator: Box<dyn SlotAllocator>,
+    /// The indices of recently returned elements, with the most recent last, when using [`SearchStrategy::Lifo`].
+    /// Entries may be stale, since elements can also be taken through the allocator.
+    pub recent: Mutex<VecDeque<usize>>,
+    /// The name used to identify the pool in diagnostics.
+    pub name: Option<String>,
+    /// The tasks which are waiting for an element to be returned.
+    pub waiters: Mutex<WaitQueue>,
+    /// The number of registered waiters which have not yet been notified.
+    pub waiting: AtomicUsize,
+    /// The statistics which have been recorded for the pool.
+    #[cfg(feature = "stats")]
+    pub stats: PoolCounters,
+    /// The key of the pool within the global registry, if it has been registered.
+    pub registration: OnceLock<u64>,
+    /// The threads which evict idle elements from the pool, which are woken when it is dropped.
+    pub evictors: Mutex<Vec<Thread>>,
+}
 
-impl<T> Reset<T> for NoopReset {
-    fn reset(_: &mut T) {}
+impl<T> FixedPoolInner<T> {
+    /// The number of elements which are neither borrowed nor poisoned, excluding vacant elements
+    /// if there is no factory to create them.
+    fn available(&self) -> usize {
+        // Poisoned elements remain marked as pulled, so the free elements are those which are not pulled, along with
+        // those held in thread caches. Each bitset is counted once, so that concurrent changes cannot cause underflow.
+        let pulled = self.pulled_elements.count_ones(Ordering::Acquire);
+        let cached = self.cached_elements.count_ones(Ordering::Acquire);
+        let available = (self.pulled_elements.len() * usize::BITS as usize - pulled + cached)
+            .min(self.elements.capacity);
+        if self.factory.is_some() {
+            available
+        } else {
+            available.saturating_sub(self.vacant_count())
+        }
+    }
+
+    /// The number of elements which are neither pulled nor hold a value.
+    fn vacant_count(&self) -> usize {
+        (0..self.pulled_elements.len())
+            .map(|i| {
+                let pulled = self.pulled_elements.word(i).load(Ordering::Acquire);
+                let occupied = self.occupied_elements.word(i).load(Ordering::Relaxed);
+                (!pulled & !occupied).count_ones() as usize
+            })
+            .sum()
+    }
+
+    /// The number of elements which are currently borrowed.
+    fn in_use(&self) -> usize {
+        let pulled = self.pulled_elements.count_ones(Ordering::Acquire);
+        let cached = self.cached_elements.count_ones(Ordering::Acquire);
+        let padding = self.pulled_elements.len() * usize::BITS as usize - self.elements.capacity;
+        pulled.saturating_sub(self.poisoned_count() + cached + padding)
+    }
+
+    /// The number of elements which are currently poisoned.
+    fn poisoned_count(&self) -> usize {
+        self.poisoned_elements.count_ones(Ordering::Acquire)
+    }
+
+    /// Marks a free element as in use and ensures that it holds a value, returning its index.
+    fn acquire(&self) -> Option<usize> {
+        let index = self.try_acquire(self.factory.is_some(), false)?;
+        unsafe {
+            self.fill(index);
+        }
+        Some(index)
+    }
+
+    /// Marks free elements as in use until the given list holds `count` indices, and ensures that they all hold
+    /// values. Returns `false` if too few elements are free, in which case the caller must release those in the list.
+    fn acquire_many(&self, count: usize, indices: &mut Vec<usize>) -> bool {
+        let slots = self.slots();
+        let mut claims = Vec::new();
+        self.allocator
+            .acquire_many(&slots, count - indices.len(), true, &mut claims);
+
+        let mut skipped = Vec::new();
+        for claim in claims {
+            if self.is_occupied(claim.index) {
+                indices.push(claim.index);
+            } else {
+                skipped.push(claim.index);
+            }
+        }
+
+        for index in skipped {
+            unsafe {
+                self.release(index);
+            }
+        }
+
+        while indices.len() < count {
+            match self.try_acquire(self.factory.is_some(), false) {
+                Some(index) => indices.push(index),
+                None => return false,
+            }
+        }
+
+        for &index in indices.iter() {
+            if !self.is_occupied(index) {
+                let factory = self
+                    .factory
+                    .as_ref()
+                    .expect("Pool had vacant element but no factory.");
+                unsafe {
+                    self.insert(index, factory());
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Creates a value for the element at the given index if it does not have one yet.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller.
+    unsafe fn fill(&self, index: usize) {
+        if !self.is_occupied(index) {
+            let factory = self
+                .factory
+                .as_ref()
+                .expect("Pool had vacant element but no factory.");
+
+            match catch_unwind(AssertUnwindSafe(factory)) {
+                Ok(value) => self.insert(index, value),
+                Err(payload) => {
+                    self.release(index);
+                    resume_unwind(payload);
+                }
+            }
+        }
+    }
+
+    /// Places a value into the vacant element at the given index.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller.
+    unsafe fn insert(&self, index: usize, value: T) {
+        let slot = self.elements.get_or_allocate(index);
+        *slot.value.get() = Some(value);
+        *slot.returned_at.get() = Instant::now();
+        self.occupied_elements.insert(index, Ordering::Relaxed);
+        self.live.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Removes the value from the element at the given index, leaving it vacant.
+    /// The caller is responsible for updating the count of live elements.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller.
+    unsafe fn take(&self, index: usize) -> Option<T> {
+        self.occupied_elements.remove(index, Ordering::Relaxed);
+        (*self.elements.get_or_allocate(index).value.get()).take()
+    }
+
+    /// Records that the element at the given index was borrowed from the given location.
+    #[cfg(feature = "leak-detection")]
+    fn track(&self, index: usize, caller: Caller) {
+        let site = BorrowSite {
+            location: caller.location,
+            pulled_at: Instant::now(),
+            backtrace: Arc::new(std::backtrace::Backtrace::capture()),
+        };
+        unsafe {
+            *self
+                .elements
+                .get_unchecked(index)
+                .borrow_site
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(site);
+        }
+    }
+
+    /// Records that the element at the given index is no longer borrowed.
+    #[cfg(feature = "leak-detection")]
+    fn untrack(&self, index: usize) {
+        unsafe {
+            *self
+                .elements
+                .get_unchecked(index)
+                .borrow_site
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = None;
+        }
+    }
+
+    /// Gets a pointer to the value of the element at the given index.
+    ///
+    /// # Safety
+    ///
+    /// The element must hold a value.
+    unsafe fn value(&self, index: usize) -> *mut T {
+        (*self.elements.get_unchecked(index).value.get())
+            .as_mut()
+            .unwrap_unchecked()
+    }
+
+    /// Whether the element at the given index holds a value. This is only guaranteed to
+    /// be accurate if the element is pulled by the caller.
+    fn is_occupied(&self, index: usize) -> bool {
+        self.occupied_elements.contains(index, Ordering::Relaxed)
+    }
+
+    /// Records that the pulled element at the given index is being returned to the pool.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller.
+    unsafe fn mark_returned(&self, index: usize) {
+        if self.idle_timeout.is_some() {
+            *self.elements.get_unchecked(index).returned_at.get() = Instant::now();
+        }
+    }
+
+    /// Withholds the pulled element at the given index from the pool by leaving it marked as in use.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller, and must not be accessed by it afterward.
+    unsafe fn poison(&self, index: usize) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(pool = self.name.as_deref(), index, "poisoned pool element");
+        self.poisoned_elements.insert(index, Ordering::Release);
+    }
+
+    /// Whether the element at the given index has been poisoned.
+    fn is_poisoned(&self, index: usize) -> bool {
+        self.poisoned_elements.contains(index, Ordering::Acquire)
+    }
+
+    /// Drops elements which have gone unborrowed for longer than the idle timeout, returning how many were dropped.
+    /// Nothing is dropped if the elements could not be recreated afterward.
+    fn evict_idle(&self) -> usize {
+        let Some(idle_timeout) = self.idle_timeout else {
+            return 0;
+        };
+
+        if self.factory.is_none() && !self.creates_async.load(Ordering::Relaxed) {
+            return 0;
+        }
+
+        let now = Instant::now();
+        let mut evicted = 0;
+        for index in self.elements.allocated().rev() {
+            if self.live.load(Ordering::Relaxed) <= self.min_size {
+                break;
+            }
+
+            if !self.try_acquire_at(index) {
+                continue;
+            }
+
+            unsafe {
+                let slot = self.elements.get_unchecked(index);
+                let expired = self.is_occupied(index)
+                    && now.saturating_duration_since(*slot.returned_at.get()) >= idle_timeout;
+                let removed = if expired
+                    && self
+                        .live
+                        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| {
+                            (x > self.min_size).then(|| x - 1)
+                        })
+                        .is_ok()
+                {
+                    self.take(index)
+                } else {
+                    None
+                };
+
+                self.release(index);
+                if removed.is_some() {
+                    evicted += 1;
+                }
+            }
+        }
+
+        evicted
+    }
+
+    /// Gets the view of the pool's elements which is presented to its allocator.
+    fn slots(&self) -> SlotSet<'_> {
+        SlotSet {
+            pulled: &self.pulled_elements,
+            occupied: &self.occupied_elements,
+            len: self.elements.capacity,
+            exhaustive: false,
+        }
+    }
+
+    /// Marks the element at the given index as in use, returning whether it was previously free.
+    fn try_acquire_at(&self, index: usize) -> bool {
+        self.pulled_elements.insert(index, Ordering::SeqCst)
+    }
+
+    /// Marks a free element as in use, returning its index. Elements which hold a value are
+    /// preferred, and vacant elements are only considered if `vacant` is set. If `exhaustive` is set,
+    /// then a free element is found whenever there is one, even in the midst of concurrent returns.
+    fn try_acquire(&self, vacant: bool, exhaustive: bool) -> Option<usize> {
+        let slots = SlotSet {
+            exhaustive,
+            ..self.slots()
+        };
+
+        if let Some(index) = self.uncache() {
+            return Some(index);
+        }
+
+        if let Some(index) = self.try_acquire_recent() {
+            return Some(index);
+        }
+
+        if let Some(index) = self.try_acquire_allocated(&slots, true) {
+            return Some(index);
+        }
+
+        if let Some(index) = self.try_steal() {
+            return Some(index);
+        }
+
+        if vacant {
+            self.try_acquire_allocated(&slots, false)
+        } else {
+            None
+        }
+    }
+
+    /// Marks the next element chosen by the allocator as in use, returning its index. If `occupied` is set, then
+    /// vacant elements are passed over and released again.
+    fn try_acquire_allocated(&self, slots: &SlotSet<'_>, occupied: bool) -> Option<usize> {
+        let mut skipped = Vec::new();
+        let acquired = loop {
+            let Some(claim) = self.allocator.acquire(slots, occupied) else {
+                break None;
+            };
+
+            if !occupied || self.is_occupied(claim.index) {
+                break Some(claim.index);
+            }
+            skipped.push(claim.index);
+        };
+
+        for index in skipped {
+            unsafe {
+                self.release(index);
+            }
+        }
+
+        acquired
+    }
+
+    /// Marks the most recently returned element which is still free as in use, returning its index.
+    fn try_acquire_recent(&self) -> Option<usize> {
+        if self.search != SearchStrategy::Lifo {
+            return None;
+        }
+
+        let mut recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+        while let Some(index) = recent.pop_back() {
+            if self.try_acquire_at(index) {
+                if self.is_occupied(index) {
+                    return Some(index);
+                }
+
+                unsafe {
+                    self.release(index);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Keeps the returned element at the given index marked as in use, so that the current thread's next pull may
+    /// take it without searching the pool. Returns `false` if the element was not cached and should be released.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller, must hold a value, and must not be accessed by it afterward.
+    unsafe fn cache(this: &Arc<Self>, index: usize) -> bool {
+        if this.thread_cache == 0 || this.waiting.load(Ordering::SeqCst) > 0 {
+            return false;
+        }
+
+        let pool = Arc::as_ptr(this) as *const ();
+        let cached = SLOT_CACHE
+            .try_with(|cache| {
+                let Ok(mut cache) = cache.try_borrow_mut() else {
+                    return false;
+                };
+
+                let position = match cache.iter().position(|x| x.pool == pool) {
+                    Some(position) => position,
+                    None => {
+                        cache.retain(|x| !(x.is_dropped)(x.pool));
+                        cache.push(ThreadCache {
+                            pool: Weak::into_raw(Arc::downgrade(this)) as *const (),
+                            indices: Vec::with_capacity(this.thread_cache),
+                            release: Self::release_cached,
+                            is_dropped: Self::is_dropped,
+                            drop_pool: Self::drop_pool,
+                        });
+                        cache.len() - 1
+                    }
+                };
+
+                let entry = &mut cache[position];
+                if entry.indices.len() < this.thread_cache {
+                    entry.indices.push(index);
+                    true
+                } else {
+                    false
+                }
+            })
+            .unwrap_or(false);
+
+        if !cached {
+            return false;
+        }
+
+        this.cached_elements.insert(index, Ordering::SeqCst);
+        if this.waiting.load(Ordering::SeqCst) > 0
+            && this.cached_elements.remove(index, Ordering::AcqRel)
+        {
+            this.release_returned(index);
+        }
+
+        true
+    }
+
+    /// Takes an element which the current thread cached when returning it, if there is one.
+    fn uncache(&self) -> Option<usize> {
+        if self.thread_cache == 0 {
+            return None;
+        }
+
+        let pool = self as *const Self as *const ();
+        SLOT_CACHE
+            .try_with(|cache| {
+                let mut cache = cache.try_borrow_mut().ok()?;
+                let entry = cache.iter_mut().find(|x| x.pool == pool)?;
+                while let Some(index) = entry.indices.pop() {
+                    if self.cached_elements.remove(index, Ordering::Acquire) {
+                        return Some(index);
+                    }
+                }
+                None
+            })
+            .ok()
+            .flatten()
+    }
+
+    /// Takes an element which another thread has cached, if there is one.
+    fn try_steal(&self) -> Option<usize> {
+        if self.thread_cache == 0 {
+            return None;
+        }
+
+        self.cached_elements
+            .ones(Ordering::Relaxed)
+            .find(|&index| self.cached_elements.remove(index, Ordering::AcqRel))
+    }
+
+    /// Releases an element cached by a thread which has exited, if the pool still exists.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have been produced by [`Weak::into_raw`] for a pool of this type.
+    unsafe fn release_cached(pool: *const (), index: usize) {
+        let pool = ManuallyDrop::new(Weak::from_raw(pool as *const Self));
+        if let Some(inner) = pool.upgrade() {
+            if inner.cached_elements.remove(index, Ordering::AcqRel) {
+                inner.release(index);
+            }
+        }
+    }
+
+    /// Whether the pool referred to by a thread cache has been dropped.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have been produced by [`Weak::into_raw`] for a pool of this type.
+    unsafe fn is_dropped(pool: *const ()) -> bool {
+        let pool = ManuallyDrop::new(Weak::from_raw(pool as *const Self));
+        pool.strong_count() == 0
+    }
+
+    /// Drops the weak reference held by a thread cache.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have been produced by [`Weak::into_raw`] for a pool of this type, and must not be used afterward.
+    unsafe fn drop_pool(pool: *const ()) {
+        drop(Weak::from_raw(pool as *const Self));
+    }
+
+    /// Marks the element at the given index as free, and wakes a waiting task if there is one.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller, and must not be accessed by it afterward.
+    unsafe fn release(&self, index: usize) {
+        self.free(index, false);
+    }
+
+    /// Marks the element at the given index as free after its borrower has returned it, and wakes a waiting task
+    /// if there is one. Unlike [`FixedPoolInner::release`], this records the element as the most recently returned.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller, and must not be accessed by it afterward.
+    unsafe fn release_returned(&self, index: usize) {
+        self.free(index, true);
+    }
+
+    /// Marks the element at the given index as free, and wakes a waiting task if there is one. If `returned` is
+    /// set, the element was returned by its borrower, rather than pulled and released by the pool itself.
+    ///
+    /// # Safety
+    ///
+    /// The element must currently be pulled by the caller, and must not be accessed by it afterward.
+    unsafe fn free(&self, index: usize, returned: bool) {
+        let occupied = self.is_occupied(index);
+        let usable = self.factory.is_some() || occupied;
+        if self.fair && self.waiting.load(Ordering::SeqCst) > 0 && self.grant(index, usable) {
+            return;
+        }
+
+        self.pulled_elements.remove(index, Ordering::SeqCst);
+        self.allocator.release(&self.slots(), index);
+
+        if returned && self.search == SearchStrategy::Lifo && occupied {
+            let mut recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+            if recent.len() >= self.elements.capacity {
+                recent.pop_front();
+            }
+            recent.push_back(index);
+        }
+
+        if self.waiting.load(Ordering::SeqCst) > 0 {
+            self.notify_one(usable);
+        }
+    }
+
+    /// Wakes the longest-waiting task which has not yet been notified. If `usable` is false, then the
+    /// freed element is vacant and cannot be filled by the factory, so only tasks which accept vacant elements are considered.
+    fn notify_one(&self, usable: bool) {
+        let mut waiters = self.waiters.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(entry) = waiters
+            .entries
+            .iter_mut()
+            .find(|x| !x.notified && (usable || x.vacant))
+        {
+            entry.notified = true;
+            entry.notified_vacant = !usable;
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+            let waker = entry.waker.clone();
+            drop(waiters);
+            waker.wake();
+        }
+    }
+
+    /// Hands the pulled element at the given index directly to the longest-waiting task which has not
+    /// yet been notified and is able to use it, then wakes that task. Returns `false` if there was no such task.
+    fn grant(&self, index: usize, usable: bool) -> bool {
+        let mut waiters = self.waiters.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(entry) = waiters
+            .entries
+            .iter_mut()
+            .find(|x| !x.notified && (usable || x.vacant))
+        {
+            entry.notified = true;
+            entry.notified_vacant = !usable;
+            entry.granted = Some(index);
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+            let waker = entry.waker.clone();
+            drop(waiters);
+            waker.wake();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Adds a waiter to the back of the queue and returns its identifier. The caller must
+    /// attempt to acquire an element afterward, since one may have been returned in the meantime.
+    fn register(&self, waker: &Waker, vacant: bool) -> u64 {
+        let mut waiters = self.waiters.lock().unwrap_or_else(PoisonError::into_inner);
+        let id = waiters.next_id;
+        waiters.next_id += 1;
+        waiters.entries.push_back(WaitEntry {
+            id,
+            waker: waker.clone(),
+            vacant,
+            notified: false,
+            notified_vacant: false,
+            granted: None,
+        });
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        drop(waiters);
+        fence(Ordering::SeqCst);
+        id
+    }
+
+    /// Re-arms a registered waiter with the given waker while keeping its place in the queue. If an element
+    /// was handed to the waiter, its index is returned instead. Otherwise, the caller must attempt to acquire
+    /// an element afterward, since one may have been returned in the meantime.
+    fn reregister(&self, id: u64, waker: &Waker) -> Option<usize> {
+        let mut waiters = self.waiters.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(entry) = waiters.entries.iter_mut().find(|x| x.id == id) {
+            if let Some(index) = entry.granted.take() {
+                return Some(index);
+            }
+
+            if entry.notified {
+                entry.notified = false;
+                self.waiting.fetch_add(1, Ordering::SeqCst);
+            }
+
+            if !entry.waker.will_wake(waker) {
+                entry.waker = waker.clone();
+            }
+        }
+        drop(waiters);
+        fence(Ordering::SeqCst);
+        None
+    }
+
+    /// Marks a registered waiter as notified while it checks an element which it has acquired, so that it is not
+    /// woken or handed another element in the meantime. The waiter keeps its place in the queue, and is re-armed
+    /// by [`FixedPoolInner::reregister`] if it goes on waiting.
+    fn hold(&self, id: u64) {
+        let mut waiters = self.waiters.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(entry) = waiters.entries.iter_mut().find(|x| x.id == id) {
+            if !entry.notified {
+                entry.notified = true;
+                entry.notified_vacant = false;
+                self.waiting.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// Removes a waiter from the queue. If the waiter was notified but gave up without acquiring
+    /// an element, the notification is passed on to the next waiter so that it is not lost. Likewise,
+    /// an element which was handed to the waiter but not taken is released again.
+    fn deregister(&self, id: u64, acquired: bool) {
+        let mut waiters = self.waiters.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(position) = waiters.entries.iter().position(|x| x.id == id) {
+            let entry = waiters
+                .entries
+                .remove(position)
+                .expect("Waiter position was out of bounds.");
+            drop(waiters);
+
+            if let Some(index) = entry.granted {
+                unsafe {
+                    self.release(index);
+                }
+            } else if !entry.notified {
+                self.waiting.fetch_sub(1, Ordering::SeqCst);
+            } else if !acquired {
+                self.notify_one(!entry.notified_vacant);
+            }
+        }
+    }
+}
+
+impl<T> Drop for FixedPoolInner<T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.registration.get() {
+            REGISTRY
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(id);
+        }
+
+        for evictor in self
+            .evictors
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            evictor.unpark();
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for FixedPoolInner<T> {}
+unsafe impl<T: Sync> Sync for FixedPoolInner<T> {}
+
+/// The pools which have been added to the registry, keyed by the order in which they were registered.
+static REGISTRY: Mutex<BTreeMap<u64, Weak<dyn Diagnose + Send + Sync>>> =
+    Mutex::new(BTreeMap::new());
+
+/// The key which will be given to the next pool added to the registry.
+static NEXT_REGISTRATION: AtomicU64 = AtomicU64::new(0);
+
+/// Describes every live pool which has been [registered](FixedPool::register), in the order that they were registered.
+pub fn registered_pools() -> Vec<PoolInfo> {
+    let pools = REGISTRY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .values()
+        .filter_map(Weak::upgrade)
+        .collect::<Vec<_>>();
+    pools.iter().map(|pool| pool.info()).collect()
+}
+
+/// A snapshot of a pool's state, for use in diagnostics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PoolInfo {
+    /// The name given to the pool by its builder, if any.
+    pub name: Option<String>,
+    /// The maximum number of elements which the pool can hold.
+    pub capacity: usize,
+    /// The number of elements which are neither borrowed nor poisoned.
+    pub available: usize,
+    /// The number of elements which are currently borrowed.
+    pub in_use: usize,
+    /// The number of elements which are currently poisoned.
+    pub poisoned: usize,
+    /// The statistics which the pool has recorded since its creation.
+    #[cfg(feature = "stats")]
+    pub stats: PoolStats,
+}
+
+/// Describes a pool without knowledge of its element type.
+trait Diagnose {
+    /// Takes a snapshot of the pool's state.
+    fn info(&self) -> PoolInfo;
+}
+
+impl<T> Diagnose for FixedPoolInner<T> {
+    fn info(&self) -> PoolInfo {
+        PoolInfo {
+            name: self.name.clone(),
+            capacity: self.elements.capacity,
+            available: self.available(),
+            in_use: self.in_use(),
+            poisoned: self.poisoned_count(),
+            #[cfg(feature = "stats")]
+            stats: self.stats.snapshot(),
+        }
+    }
+}
+
+/// Represents an object which is borrowed from the fixed pool.
+pub struct PoolBorrow<T, R: Reset<T> = NoopReset, V: Validate<T> = NoopValidate> {
+    /// The index in the pool of the borrowed element.
+    index: usize,
+    /// The pool itself.
+    pool: FixedPool<T, R, V>,
+}
+
+impl<T, R: Reset<T>, V: Validate<T>> PoolBorrow<T, R, V> {
+    /// The index of the item within the pool.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<T, R: Reset<T>, V: Validate<T>> Deref for PoolBorrow<T, R, V> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.pool.0.value(self.index) }
+    }
+}
+
+impl<T, R: Reset<T>, V: Validate<T>> DerefMut for PoolBorrow<T, R, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.pool.0.value(self.index) }
+    }
+}
+
+impl<T, R: Reset<T>, V: Validate<T>> Drop for PoolBorrow<T, R, V> {
+    fn drop(&mut self) {
+        unsafe {
+            self.pool.restore(self.index);
+        }
+    }
+}
+
+impl<T: std::fmt::Debug, R: Reset<T>, V: Validate<T>> std::fmt::Debug for PoolBorrow<T, R, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PoolBorrow")
+            .field("index", &self.index)
+            .field("value", &**self)
+            .finish()
+    }
+}
+
+/// Represents an object which is borrowed from a fixed pool for the lifetime of a reference to the pool.
+pub struct ScopedBorrow<'a, T, R: Reset<T> = NoopReset, V: Validate<T> = NoopValidate> {
+    /// The index in the pool of the borrowed element.
+    index: usize,
+    /// The pool itself.
+    pool: &'a FixedPool<T, R, V>,
+}
+
+impl<'a, T, R: Reset<T>, V: Validate<T>> ScopedBorrow<'a, T, R, V> {
+    /// The index of the item within the pool.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<'a, T, R: Reset<T>, V: Validate<T>> Deref for ScopedBorrow<'a, T, R, V> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.pool.0.value(self.index) }
+    }
+}
+
+impl<'a, T, R: Reset<T>, V: Validate<T>> DerefMut for ScopedBorrow<'a, T, R, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.pool.0.value(self.index) }
+    }
+}
+
+impl<'a, T, R: Reset<T>, V: Validate<T>> Drop for ScopedBorrow<'a, T, R, V> {
+    fn drop(&mut self) {
+        unsafe {
+            self.pool.restore(self.index);
+        }
+    }
+}
+
+impl<'a, T: std::fmt::Debug, R: Reset<T>, V: Validate<T>> std::fmt::Debug
+    for ScopedBorrow<'a, T, R, V>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScopedBorrow")
+            .field("index", &self.index)
+            .field("value", &**self)
+            .finish()
+    }
+}
+
+/// A future which resolves to a value from the pool once one is available.
+pub struct PullFuture<'a, T, R: Reset<T> = NoopReset, V: Validate<T> = NoopValidate> {
+    /// The pool from which to pull.
+    pool: &'a FixedPool<T, R, V>,
+    /// The identifier of this future within the pool's wait queue, if it has been registered.
+    waiter: Option<u64>,
+    /// Whether this future will accept a vacant element.
+    vacant: bool,
+    /// The location from which the pull was started.
+    caller: Caller,
+}
+
+impl<'a, T, R: Reset<T>, V: Validate<T>> PullFuture<'a, T, R, V> {
+    /// Attempts to mark an element as in use, registering this future in the wait queue if none is available.
+    /// If the future is registered when it obtains an element, then it keeps its place in the queue until
+    /// [`PullFuture::complete`] is called, but is not woken or handed further elements in the meantime.
+    fn poll_acquire(&mut self, cx: &mut Context<'_>) -> Poll<usize> {
+        match self.waiter {
+            Some(id) => {
+                if let Some(index) = self.pool.0.reregister(id, cx.waker()) {
+                    return Poll::Ready(index);
+                }
+            }
+            None => {
+                if let Some(index) = self.pool.0.try_acquire(self.vacant, false) {
+                    return Poll::Ready(index);
+                }
+
+                self.waiter = Some(self.pool.0.register(cx.waker(), self.vacant));
+            }
+        }
+
+        // The search must not miss an element which was returned before this future registered, since its return
+        // may not have woken the future.
+        match self.pool.0.try_acquire(self.vacant, true) {
+            Some(index) => {
+                if let Some(id) = self.waiter {
+                    self.pool.0.hold(id);
+                }
+                Poll::Ready(index)
+            }
+            None => Poll::Pending,
+        }
+    }
+
+    /// Removes this future from the wait queue after it has obtained an element which it will keep.
+    fn complete(&mut self) {
+        if let Some(id) = self.waiter.take() {
+            self.pool.0.deregister(id, true);
+        }
+    }
+}
+
+impl<'a, T, R: Reset<T>, V: Validate<T>> Future for PullFuture<'a, T, R, V> {
+    type Output = PoolBorrow<T, R, V>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            let Poll::Ready(index) = this.poll_acquire(cx) else {
+                return Poll::Pending;
+            };
+
+            // The future stays in the wait queue until validation passes, so that it keeps its place if the
+            // element is poisoned.
+            unsafe {
+                this.pool.0.fill(index);
+                if this.pool.validate(index) {
+                    this.complete();
+                    return Poll::Ready(this.pool.borrow(index, this.caller));
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T, R: Reset<T>, V: Validate<T>> Drop for PullFuture<'a, T, R, V> {
+    fn drop(&mut self) {
+        if let Some(id) = self.waiter.take() {
+            self.pool.0.deregister(id, false);
+        }
+    }
+}
+
+impl<'a, T, R: Reset<T>, V: Validate<T>> std::fmt::Debug for PullFuture<'a, T, R, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PullFuture")
+            .field("pool", &self.pool.name())
+            .field("waiter", &self.waiter)
+            .field("vacant", &self.vacant)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Releases a vacant element if it is dropped before a value is placed into it.
+struct Vacancy<'a, T> {
+    /// The pool to which the element belongs.
+    inner: &'a FixedPoolInner<T>,
+    /// The index of the element.
+    index: usize,
+}
+
+impl<'a, T> Drop for Vacancy<'a, T> {
+    fn drop(&mut self) {
+        unsafe {
+            self.inner.release(self.index);
+        }
+    }
+}
+
+/// Releases a set of pulled elements if it is dropped before they are lent out.
+struct Batch<'a, T> {
+    /// The pool to which the elements belong.
+    inner: &'a FixedPoolInner<T>,
+    /// The indices of the elements.
+    indices: Vec<usize>,
+}
+
+impl<'a, T> Drop for Batch<'a, T> {
+    fn drop(&mut self) {
+        for &index in &self.indices {
+            unsafe {
+                self.inner.release(index);
+            }
+        }
+    }
+}
+
+/// Holds a single element of a pool. The contents are only accessed by whoever has pulled the element.
+struct Slot<T> {
+    /// The element, or `None` if it has not been created.
+    value: UnsafeCell<Option<T>>,
+    /// The time at which the element was last returned to the pool.
+    returned_at: UnsafeCell<Instant>,
+    /// The time at which the element was last pulled.
+    #[cfg(feature = "stats")]
+    pulled_at: UnsafeCell<Instant>,
+    /// Where and when the element was pulled, if it is currently borrowed.
+    #[cfg(feature = "leak-detection")]
+    borrow_site: Mutex<Option<BorrowSite>>,
+}
+
+impl<T> Slot<T> {
+    /// Creates a slot which holds the given value.
+    fn new(value: Option<T>, now: Instant) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            returned_at: UnsafeCell::new(now),
+            #[cfg(feature = "stats")]
+            pulled_at: UnsafeCell::new(now),
+            #[cfg(feature = "leak-detection")]
+            borrow_site: Mutex::new(None),
+        }
+    }
+}
+
+/// The location in the source code from which an element was pulled. This is empty unless leak detection is enabled.
+#[derive(Copy, Clone, Debug)]
+struct Caller {
+    /// The location of the caller.
+    #[cfg(feature = "leak-detection")]
+    location: &'static Location<'static>,
+}
+
+impl Caller {
+    /// Captures the location of the caller.
+    #[cfg_attr(feature = "leak-detection", track_caller)]
+    fn capture() -> Self {
+        Self {
+            #[cfg(feature = "leak-detection")]
+            location: Location::caller(),
+        }
+    }
+}
+
+/// Where and when an element was pulled.
+#[cfg(feature = "leak-detection")]
+#[derive(Clone, Debug)]
+struct BorrowSite {
+    /// The location from which the element was pulled.
+    location: &'static Location<'static>,
+    /// The time at which the element was pulled.
+    pulled_at: Instant,
+    /// The stack at the time of the pull, if backtraces are enabled.
+    backtrace: Arc<std::backtrace::Backtrace>,
+}
+
+/// Describes a borrow which has been held for longer than expected.
+#[cfg(feature = "leak-detection")]
+#[derive(Clone, Debug)]
+pub struct OutstandingBorrow {
+    /// The index of the borrowed element within the pool.
+    pub index: usize,
+    /// How long the element has been borrowed.
+    pub held_for: Duration,
+    /// The location from which the element was pulled.
+    pub location: &'static Location<'static>,
+    /// The stack at the time of the pull. This is only captured if backtraces are enabled through
+    /// the `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variables, as described by [`Backtrace`](std::backtrace::Backtrace).
+    pub backtrace: Arc<std::backtrace::Backtrace>,
+}
+
+/// The size in bytes of the region that padded bitset words are spread across. This is twice the size of a typical
+/// cache line, since many processors prefetch cache lines in adjacent pairs.
+const CACHE_LINE: usize = 128;
+
+/// The number of words in the bitset of borrowed elements beyond which a summary of the full words is kept.
+/// Smaller pools are quick enough to search without one.
+const SUMMARY_THRESHOLD: usize = 16;
+
+/// A set of bits stored in atomic words, which may each be padded to occupy their own cache line.
+struct Bitset {
+    /// The storage for the words, including any padding.
+    words: Box<[AtomicUsize]>,
+    /// The position within the storage of the first word.
+    offset: usize,
+    /// The distance within the storage between consecutive words.
+    stride: usize,
+    /// The number of words in the set.
+    len: usize,
+    /// A second level of bits, each of which is set if the corresponding word is full. A bit may also be set
+    /// briefly after its word has regained space, until [`Bitset::mark_full`] notices. This is empty if the set is
+    /// not summarized.
+    summary: Box<[AtomicUsize]>,
+}
+
+impl Bitset {
+    /// Creates a set with the given number of words, each initialized by the given function.
+    fn new(len: usize, padded: bool, mut init: impl FnMut(usize) -> usize) -> Self {
+        let stride = if padded {
+            CACHE_LINE / size_of::<AtomicUsize>()
+        } else {
+            1
+        };
+
+        let words = (0..(len + 1) * stride - 1)
+            .map(|_| AtomicUsize::new(0))
+            .collect::<Box<[_]>>();
+        let offset = if padded {
+            words.as_ptr().align_offset(CACHE_LINE).min(stride - 1)
+        } else {
+            0
+        };
+
+        let result = Self {
+            words,
+            offset,
+            stride,
+            len,
+            summary: Box::default(),
+        };
+
+        for i in 0..len {
+            result.word(i).store(init(i), Ordering::Relaxed);
+        }
+
+        result
+    }
+
+    /// Creates a set with a bit for each of the given number of slots, all of which are unset. The bits past the
+    /// last slot are set, so that they are never mistaken for free slots, and large sets are summarized.
+    fn slots(capacity: usize, padded: bool) -> Self {
+        let len = capacity.saturating_sub(1) / usize::BITS as usize + 1;
+        let result = Self::new(len, padded, |i| {
+            let remaining_elements_len = capacity
+                .saturating_sub(i * usize::BITS as usize)
+                .min(usize::BITS as usize);
+            usize::MAX
+                .checked_shl(remaining_elements_len as u32)
+                .unwrap_or(0)
+        });
+
+        if len >= SUMMARY_THRESHOLD {
+            result.with_summary()
+        } else {
+            result
+        }
+    }
+
+    /// Adds a summary level to the set, which records the words that are full so that searches may skip them.
+    fn with_summary(mut self) -> Self {
+        self.summary = (0..self.len.div_ceil(usize::BITS as usize))
+            .map(|_| AtomicUsize::new(0))
+            .collect();
+        for i in 0..self.len {
+            if self.word(i).load(Ordering::Relaxed) == usize::MAX {
+                self.summary[i / usize::BITS as usize]
+                    .fetch_or(1 << (i % usize::BITS as usize), Ordering::Relaxed);
+            }
+        }
+        self
+    }
+
+    /// The number of words in the set.
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Sets the given mask of bits in the word at the given index, returning the previous value of the word.
+    fn fetch_or(&self, index: usize, mask: usize, ordering: Ordering) -> usize {
+        let previous = self.word(index).fetch_or(mask, ordering);
+        if !self.summary.is_empty() && previous | mask == usize::MAX && previous != usize::MAX {
+            self.mark_full(index);
+        }
+        previous
+    }
+
+    /// Records in the summary that the word at the given index is full. A concurrent removal may already have
+    /// emptied part of the word again, in which case its summary bit is cleared once more. Until then, the word
+    /// is summarized as full even though it has space, so summarized searches may briefly miss its free bits.
+    fn mark_full(&self, index: usize) {
+        let summary = &self.summary[index / usize::BITS as usize];
+        let mask = 1 << (index % usize::BITS as usize);
+        summary.fetch_or(mask, Ordering::SeqCst);
+        if self.word(index).load(Ordering::SeqCst) != usize::MAX {
+            summary.fetch_and(!mask, Ordering::SeqCst);
+        }
+    }
+
+    /// Gets the indices of the words which may have unset bits, starting at the given word and wrapping around.
+    /// If `summarized` is set, then words which the summary records as full are skipped. Such a search may miss
+    /// bits which were unset while the summary was being updated, so searches which must find any unset bit
+    /// should not be summarized.
+    fn words_with_space(&self, start: usize, summarized: bool) -> impl '_ + Iterator<Item = usize> {
+        self.words_with_space_in(start, self.len, summarized)
+            .chain(self.words_with_space_in(0, start, summarized))
+    }
+
+    /// Gets the indices of the words within the given range which may have unset bits, skipping those which
+    /// the summary records as full if `summarized` is set.
+    fn words_with_space_in(
+        &self,
+        start: usize,
+        end: usize,
+        summarized: bool,
+    ) -> impl '_ + Iterator<Item = usize> {
+        let bits = usize::BITS as usize;
+        let summarized = summarized && !self.summary.is_empty();
+        let summary_range = if !summarized || start >= end {
+            0..0
+        } else {
+            start / bits..end.div_ceil(bits)
+        };
+        let unsummarized = if summarized { 0..0 } else { start..end };
+
+        unsummarized.chain(summary_range.flat_map(move |i| {
+            let low = start.max(i * bits) - i * bits;
+            let high = end.min((i + 1) * bits) - i * bits;
+            let mut free = !self.summary[i].load(Ordering::Acquire)
+                & (usize::MAX << low)
+                & (usize::MAX >> (bits - high));
+
+            std::iter::from_fn(move || {
+                let j = free.trailing_zeros() as usize;
+                free &= free.wrapping_sub(1);
+                (j < bits).then_some(i * bits + j)
+            })
+        }))
+    }
+
+    /// Gets the word at the given index.
+    fn word(&self, index: usize) -> &AtomicUsize {
+        &self.words[self.offset + index * self.stride]
+    }
+
+    /// Whether the given bit is set.
+    fn contains(&self, index: usize, ordering: Ordering) -> bool {
+        let mask = 1 << (index % usize::BITS as usize);
+        (self.word(index / usize::BITS as usize).load(ordering) & mask) != 0
+    }
+
+    /// Sets the given bit, returning whether it was previously unset.
+    fn insert(&self, index: usize, ordering: Ordering) -> bool {
+        let mask = 1 << (index % usize::BITS as usize);
+        (self.fetch_or(index / usize::BITS as usize, mask, ordering) & mask) == 0
+    }
+
+    /// Unsets the given bit, returning whether it was previously set.
+    fn remove(&self, index: usize, ordering: Ordering) -> bool {
+        let usize_index = index / usize::BITS as usize;
+        let mask = 1 << (index % usize::BITS as usize);
+        let previous = self.word(usize_index).fetch_and(!mask, ordering);
+        if !self.summary.is_empty() && previous == usize::MAX {
+            self.summary[usize_index / usize::BITS as usize].fetch_and(
+                !(1 << (usize_index % usize::BITS as usize)),
+                Ordering::SeqCst,
+            );
+        }
+        (previous & mask) != 0
+    }
+
+    /// Counts the bits which are set.
+    fn count_ones(&self, ordering: Ordering) -> usize {
+        (0..self.len)
+            .map(|i| self.word(i).load(ordering).count_ones() as usize)
+            .sum()
+    }
+
+    /// Gets the indices of the bits which are set.
+    fn ones(&self, ordering: Ordering) -> impl '_ + Iterator<Item = usize> {
+        (0..self.len).flat_map(move |usize_index| {
+            let present_value = self.word(usize_index).load(ordering);
+            (0..usize::BITS as usize)
+                .filter(move |i| present_value & (1 << i) != 0)
+                .map(move |i| usize_index * usize::BITS as usize + i)
+        })
+    }
+}
+
+/// Holds a contiguous run of elements, which is allocated upon first use.
+type Segment<T> = OnceLock<Box<[Slot<T>]>>;
+
+/// Stores the elements of a pool in segments of doubling length, which are allocated as the pool grows.
+/// Segments are never moved once allocated, so references to their elements remain valid.
+struct Segments<T> {
+    /// The length of the first segment.
+    base: usize,
+    /// The total number of slots across all segments.
+    capacity: usize,
+    /// The segments, each of which is allocated upon first use.
+    segments: Box<[Segment<T>]>,
+}
+
+impl<T> Segments<T> {
+    /// Creates storage with room for `capacity` elements, the first of which are the given values.
+    fn new(initial: Vec<T>, capacity: usize) -> Self {
+        let base = initial.len().max(1);
+        let mut segments = Vec::new();
+        while base * ((1 << segments.len()) - 1) < capacity {
+            segments.push(OnceLock::new());
+        }
+
+        let result = Self {
+            base,
+            capacity,
+            segments: segments.into_boxed_slice(),
+        };
+
+        if !initial.is_empty() {
+            let now = Instant::now();
+            let _ = result.segments[0].set(
+                initial
+                    .into_iter()
+                    .map(|x| Slot::new(Some(x), now))
+                    .collect(),
+            );
+        }
+
+        result
+    }
+
+    /// Gets the segment and offset within that segment at which the given index resides.
+    fn locate(&self, index: usize) -> (usize, usize) {
+        if index < self.base {
+            return (0, index);
+        }
+
+        let segment = (index / self.base + 1).ilog2() as usize;
+        (segment, index - self.base * ((1 << segment) - 1))
+    }
+
+    /// Gets the slot at the given index, allocating its segment if necessary.
+    fn get_or_allocate(&self, index: usize) -> &Slot<T> {
+        let (segment, offset) = self.locate(index);
+        let start = index - offset;
+        let len = (self.base << segment).min(self.capacity - start);
+        &self.segments[segment].get_or_init(|| {
+            let now = Instant::now();
+            (0..len).map(|_| Slot::new(None, now)).collect()
+        })[offset]
+    }
+
+    /// Gets the indices of all slots whose segments have been allocated.
+    fn allocated(&self) -> impl '_ + DoubleEndedIterator<Item = usize> {
+        self.segments
+            .iter()
+            .enumerate()
+            .filter_map(|(segment, slots)| {
+                let start = self.base * ((1 << segment) - 1);
+                slots.get().map(|x| start..start + x.len())
+            })
+            .flatten()
+    }
+
+    /// Gets the slot at the given index without bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// The segment containing the index must already be allocated.
+    unsafe fn get_unchecked(&self, index: usize) -> &Slot<T> {
+        let (segment, offset) = self.locate(index);
+        self.segments
+            .get_unchecked(segment)
+            .get()
+            .unwrap_unchecked()
+            .get_unchecked(offset)
+    }
+}
+
+/// Holds the set of tasks which are waiting for an element to be returned.
+#[derive(Debug, Default)]
+struct WaitQueue {
+    /// The identifier to assign to the next waiter.
+    next_id: u64,
+    /// The registered waiters, in the order that they began waiting.
+    entries: VecDeque<WaitEntry>,
+}
+
+/// Describes a task which is waiting for an element to be returned.
+#[derive(Debug)]
+struct WaitEntry {
+    /// The identifier of the waiter.
+    id: u64,
+    /// The waker to invoke when an element becomes available.
+    waker: Waker,
+    /// Whether the waiter will accept a vacant element.
+    vacant: bool,
+    /// Whether the waiter has been woken since it last tried to acquire an element.
+    notified: bool,
+    /// Whether the waiter was woken for a vacant element that only it could use.
+    notified_vacant: bool,
+    /// The index of an element which was handed directly to the waiter, if any.
+    granted: Option<usize>,
+}
+
+/// Wakes a thread which is blocked waiting for an element to be returned.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// The number of buckets in a hold-time histogram.
+#[cfg(feature = "stats")]
+const HOLD_TIME_BUCKETS: usize = 32;
+
+/// The counters with which a pool records its statistics.
+#[cfg(feature = "stats")]
+#[derive(Debug, Default)]
+struct PoolCounters {
+    /// The number of successful pulls.
+    pulls: AtomicU64,
+    /// The number of pulls which found no available element.
+    failed_pulls: AtomicU64,
+    /// The number of elements which are currently borrowed.
+    borrowed: AtomicUsize,
+    /// The greatest number of elements which have been borrowed at once.
+    peak_borrowed: AtomicUsize,
+    /// The number of borrows which fall into each bucket of the hold-time histogram.
+    hold_times: [AtomicU64; HOLD_TIME_BUCKETS],
+}
+
+#[cfg(feature = "stats")]
+impl PoolCounters {
+    /// Records that an element was successfully pulled.
+    fn record_pull(&self) {
+        self.pulls.fetch_add(1, Ordering::Relaxed);
+        let borrowed = self.borrowed.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak_borrowed.fetch_max(borrowed, Ordering::Relaxed);
+    }
+
+    /// Records that a pull found no available element.
+    fn record_failure(&self) {
+        self.failed_pulls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a borrow held for the given duration was returned.
+    fn record_return(&self, held: Duration) {
+        self.borrowed.fetch_sub(1, Ordering::Relaxed);
+        self.hold_times[HoldTimeHistogram::bucket(held)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Copies the current values of the counters.
+    fn snapshot(&self) -> PoolStats {
+        PoolStats {
+            pulls: self.pulls.load(Ordering::Relaxed),
+            failed_pulls: self.failed_pulls.load(Ordering::Relaxed),
+            borrowed: self.borrowed.load(Ordering::Relaxed),
+            peak_borrowed: self.peak_borrowed.load(Ordering::Relaxed),
+            hold_times: HoldTimeHistogram {
+                counts: std::array::from_fn(|i| self.hold_times[i].load(Ordering::Relaxed)),
+            },
+        }
+    }
+}
+
+/// A snapshot of the statistics which a pool has recorded since its creation.
+#[cfg(feature = "stats")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// The number of pulls which produced a borrow.
+    pub pulls: u64,
+    /// The number of pulls which returned `None` because no element was available.
+    pub failed_pulls: u64,
+    /// The number of elements which were borrowed when the snapshot was taken.
+    pub borrowed: usize,
+    /// The greatest number of elements which have been borrowed at once.
+    pub peak_borrowed: usize,
+    /// How long returned borrows were held, measured from pull to drop.
+    pub hold_times: HoldTimeHistogram,
+}
+
+/// A histogram of borrow durations. Bucket `0` counts borrows held for less than a microsecond, and each bucket `i`
+/// after it counts borrows held for at least `2^(i - 1)` and less than `2^i` microseconds. The final bucket also
+/// counts every longer borrow.
+#[cfg(feature = "stats")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HoldTimeHistogram {
+    /// The number of borrows in each bucket.
+    counts: [u64; HOLD_TIME_BUCKETS],
+}
+
+#[cfg(feature = "stats")]
+impl HoldTimeHistogram {
+    /// Gets the upper bound and number of borrows for each bucket, in order. The final bucket's bound is [`Duration::MAX`].
+    pub fn buckets(&self) -> impl '_ + Iterator<Item = (Duration, u64)> {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| (Self::upper_bound(i), count))
+    }
+
+    /// The total number of borrows which have been recorded.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Estimates the given quantile, between `0.0` and `1.0`, of the hold times by returning the upper bound of the
+    /// bucket in which it falls. Returns `None` if no borrows have been recorded.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        assert!(
+            (0.0..=1.0).contains(&quantile),
+            "Quantile must be between zero and one."
+        );
+
+        let target = ((self.count() as f64 * quantile).ceil() as u64).max(1);
+        let mut seen = 0;
+        self.buckets().find_map(|(bound, count)| {
+            seen += count;
+            (seen >= target).then_some(bound)
+        })
+    }
+
+    /// Gets the index of the bucket into which the given duration falls.
+    fn bucket(held: Duration) -> usize {
+        let micros = held.as_micros();
+        ((u128::BITS - micros.leading_zeros()) as usize).min(HOLD_TIME_BUCKETS - 1)
+    }
+
+    /// Gets the exclusive upper bound of the bucket at the given index.
+    fn upper_bound(index: usize) -> Duration {
+        if index + 1 < HOLD_TIME_BUCKETS {
+            Duration::from_micros(1 << index)
+        } else {
+            Duration::MAX
+        }
+    }
+}
+
+/// Determines how a pull chooses which free element to take.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SearchStrategy {
+    /// Every search begins at the first element, so the lowest-indexed free element is always chosen.
+    #[default]
+    LowestIndex,
+    /// Each search begins one word of elements after the previous one, spreading pulls evenly across the pool.
+    RoundRobin,
+    /// Each thread begins searching where it last found a free element, or at a position derived from its
+    /// thread ID if it has not pulled before. Threads thereby tend to keep to separate parts of the pool.
+    PerThread,
+    /// The most recently returned element is chosen first, so that its memory is likely to still be in cache.
+    /// If none of the returned elements are free, the pool's allocator chooses, which by default takes the
+    /// lowest-indexed free element. Returns and pulls
+    /// share a lock, so this strategy suits pools of large objects better than heavily contended ones.
+    Lifo,
+}
+
+thread_local! {
+    /// The elements which the current thread has cached from each pool.
+    static SLOT_CACHE: RefCell<Vec<ThreadCache>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The elements which the current thread has cached from a single pool. Any which remain are
+/// returned to the pool when the thread exits.
+struct ThreadCache {
+    /// A weak reference to the pool's inner state, with its type erased.
+    pool: *const (),
+    /// The indices of the cached elements. Elements which have since been taken by other threads may remain in this list.
+    indices: Vec<usize>,
+    /// Returns a cached element to the pool, if the pool still exists.
+    release: unsafe fn(*const (), usize),
+    /// Whether the pool has been dropped.
+    is_dropped: unsafe fn(*const ()) -> bool,
+    /// Drops the weak reference to the pool.
+    drop_pool: unsafe fn(*const ()),
+}
+
+impl Drop for ThreadCache {
+    fn drop(&mut self) {
+        unsafe {
+            for &index in &self.indices {
+                (self.release)(self.pool, index);
+            }
+
+            (self.drop_pool)(self.pool);
+        }
+    }
+}
+
+/// The shard which the next thread to use a sharded pool will pull from first.
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The shard from which the current thread pulls first, before reducing by the number of shards.
+    /// Threads are assigned shards in turn, so that they spread evenly across them.
+    static SHARD_HINT: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed);
+}
+
+thread_local! {
+    /// The slot at which the current thread begins searching when using [`SearchStrategy::PerThread`].
+    static SEARCH_HINT: Cell<usize> = Cell::new({
+        let mut hasher = DefaultHasher::new();
+        current().id().hash(&mut hasher);
+        hasher.finish() as usize
+    });
+}
+
+/// Chooses which free elements a pool's pulls take. The pool presents its elements to the allocator as a [`SlotSet`],
+/// which records the slots that are in use, and the allocator hands out a slot by claiming it there. An allocator
+/// therefore cannot cause an element to be borrowed twice, and is free to keep whatever bookkeeping it likes about
+/// which slots are likely to be free.
+///
+/// When a pool is created, it releases each of its slots to the allocator in turn, from the last to the first.
+/// Afterward, it calls [`SlotAllocator::release`] whenever a slot becomes free, whether or not the allocator handed
+/// it out. A slot may also be claimed by the pool itself, such as when [evicting](FixedPool::evict_idle) idle
+/// elements, so an allocator must tolerate slots which it believes to be free being in use.
+pub trait SlotAllocator: Send + Sync {
+    /// Claims a free slot and returns it, or returns `None` if no suitable slot is free. If `occupied` is set, then
+    /// only slots which hold a value are wanted; a vacant slot which is returned anyway is released again.
+    fn acquire<'a>(&self, slots: &'a SlotSet<'_>, occupied: bool) -> Option<Claim<'a>>;
+
+    /// Claims free slots until `claims` holds `count` of them, or no suitable slots remain. By default, this
+    /// calls [`SlotAllocator::acquire`] repeatedly.
+    fn acquire_many<'a>(
+        &self,
+        slots: &'a SlotSet<'_>,
+        count: usize,
+        occupied: bool,
+        claims: &mut Vec<Claim<'a>>,
+    ) {
+        while claims.len() < count {
+            match self.acquire(slots, occupied) {
+                Some(claim) => claims.push(claim),
+                None => return,
+            }
+        }
+    }
+
+    /// Records that the slot at the given index has become free.
+    fn release(&self, slots: &SlotSet<'_>, index: usize);
+
+    /// The number of slots which the allocator manages.
+    fn len(&self) -> usize;
+
+    /// Whether the allocator manages no slots.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The slots of a pool, as presented to its [`SlotAllocator`]. The set records which slots are in use, and
+/// a slot is handed out by claiming it here.
+pub struct SlotSet<'a> {
+    /// A bitset representing the slots which are in use.
+    pulled: &'a Bitset,
+    /// A bitset representing the slots which hold a value.
+    occupied: &'a Bitset,
+    /// The number of slots.
+    len: usize,
+    /// Whether searches must examine every word of bits, rather than skipping those which appear to be full.
+    exhaustive: bool,
+}
+
+impl<'a> SlotSet<'a> {
+    /// The number of slots.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no slots.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the slot at the given index is free.
+    pub fn is_free(&self, index: usize) -> bool {
+        assert!(index < self.len, "Index was out of bounds for slot set.");
+        !self.pulled.contains(index, Ordering::Acquire)
+    }
+
+    /// Whether the slot at the given index holds a value. A slot's value only changes while it is in use,
+    /// so this is accurate for free slots until they are claimed.
+    pub fn holds_value(&self, index: usize) -> bool {
+        assert!(index < self.len, "Index was out of bounds for slot set.");
+        self.occupied.contains(index, Ordering::Relaxed)
+    }
+
+    /// Claims the slot at the given index, or returns `None` if it is in use.
+    pub fn try_claim(&self, index: usize) -> Option<Claim<'_>> {
+        assert!(index < self.len, "Index was out of bounds for slot set.");
+        self.pulled
+            .insert(index, Ordering::SeqCst)
+            .then(|| Claim::new(index))
+    }
+
+    /// Claims the first free slot found by searching from the given index and wrapping around, or returns `None` if
+    /// every slot is in use. Slots are searched a word of bits at a time, beginning with the word which contains the
+    /// given index. If `occupied` is set, then only slots which appear to hold a value are considered.
+    ///
+    /// In large pools, the search skips words which appear to be full, and so may miss a slot which is freed while
+    /// it runs. When a pull is about to wait for an element to be returned, the pool instead presents a set whose
+    /// searches examine every word, so that no free slot is missed.
+    pub fn claim_first(&self, start: usize, occupied: bool) -> Option<Claim<'_>> {
+        for usize_index in self
+            .pulled
+            .words_with_space(self.start_word(start), !self.exhaustive)
+        {
+            let mut present_value = self.free_mask(usize_index, occupied);
+            while present_value != usize::MAX {
+                let next_zero = present_value.trailing_ones() as usize;
+                let mask = 1 << next_zero;
+                let previous = self.pulled.fetch_or(usize_index, mask, Ordering::AcqRel);
+                if previous & mask == 0 {
+                    return Some(Claim::new(usize_index * usize::BITS as usize + next_zero));
+                }
+                present_value |= previous;
+            }
+        }
+
+        None
+    }
+
+    /// Claims free slots until `claims` holds `count` of them, or every slot is in use, searching in the same way as
+    /// [`SlotSet::claim_first`]. Free slots which share a word of bits are claimed together with a single atomic operation.
+    pub fn claim_many(
+        &self,
+        start: usize,
+        count: usize,
+        occupied: bool,
+        claims: &mut Vec<Claim<'_>>,
+    ) {
+        for usize_index in self
+            .pulled
+            .words_with_space(self.start_word(start), !self.exhaustive)
+        {
+            let mut present_value = self.free_mask(usize_index, occupied);
+            while claims.len() < count && present_value != usize::MAX {
+                let mut free = !present_value;
+                let mut mask = 0;
+                let mut taken = 0;
+                while free != 0 && taken < count - claims.len() {
+                    mask |= free & free.wrapping_neg();
+                    free &= free.wrapping_sub(1);
+                    taken += 1;
+                }
+
+                let previous = self.pulled.fetch_or(usize_index, mask, Ordering::AcqRel);
+                present_value |= previous | mask;
+
+                let mut claimed = mask & !previous;
+                while claimed != 0 {
+                    claims.push(Claim::new(
+                        usize_index * usize::BITS as usize + claimed.trailing_zeros() as usize,
+                    ));
+                    claimed &= claimed - 1;
+                }
+            }
+
+            if claims.len() == count {
+                return;
+            }
+        }
+    }
+
+    /// Gets the word of bits which contains the given slot, wrapping around if it is out of bounds.
+    fn start_word(&self, start: usize) -> usize {
+        start.checked_rem(self.len).unwrap_or(0) / usize::BITS as usize
+    }
+
+    /// Gets the bits of the word at the given index, with each slot that cannot be claimed set. If `occupied`
+    /// is set, then slots which appear to be vacant are also set.
+    fn free_mask(&self, usize_index: usize, occupied: bool) -> usize {
+        let pulled = self.pulled.word(usize_index).load(Ordering::Acquire);
+        if occupied {
+            pulled | !self.occupied.word(usize_index).load(Ordering::Relaxed)
+        } else {
+            pulled
+        }
+    }
+}
+
+impl<'a> std::fmt::Debug for SlotSet<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SlotSet")
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A slot which has been claimed from a [`SlotSet`]. The slot is handed out by returning the claim from
+/// [`SlotAllocator::acquire`]; if the claim is dropped instead, the slot stays in use.
+#[must_use]
+#[derive(Debug)]
+pub struct Claim<'a> {
+    /// The index of the slot.
+    index: usize,
+    /// Ties the claim to the set from which it was made.
+    marker: PhantomData<&'a ()>,
+}
+
+impl<'a> Claim<'a> {
+    /// Creates a claim on the slot at the given index.
+    fn new(index: usize) -> Self {
+        Self {
+            index,
+            marker: PhantomData,
+        }
+    }
+
+    /// The index of the claimed slot.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+/// A slot allocator which finds free slots by searching the pool's bitset according to a [`SearchStrategy`].
+/// This is the allocator which pools use by default.
+#[derive(Debug)]
+pub struct BitsetAllocator {
+    /// The number of slots.
+    len: usize,
+    /// Where searches begin.
+    search: SearchStrategy,
+    /// The word at which the next round-robin search begins.
+    cursor: AtomicUsize,
+}
+
+impl BitsetAllocator {
+    /// Creates an allocator for the given number of slots, which searches for free slots using the given strategy.
+    /// [`SearchStrategy::Lifo`] searches from the first slot, since pools reuse returned elements themselves before
+    /// consulting their allocator.
+    pub fn new(len: usize, search: SearchStrategy) -> Self {
+        Self {
+            len,
+            search,
+            cursor: AtomicUsize::new(0),
+        }
+    }
+
+    /// The slot at which a search begins.
+    fn start(&self, slots: &SlotSet<'_>) -> usize {
+        match self.search {
+            SearchStrategy::LowestIndex | SearchStrategy::Lifo => 0,
+            SearchStrategy::RoundRobin => {
+                let words = slots.len().div_ceil(usize::BITS as usize).max(1);
+                self.cursor.fetch_add(1, Ordering::Relaxed) % words * usize::BITS as usize
+            }
+            SearchStrategy::PerThread => SEARCH_HINT.with(Cell::get),
+        }
+    }
+}
+
+impl SlotAllocator for BitsetAllocator {
+    fn acquire<'a>(&self, slots: &'a SlotSet<'_>, occupied: bool) -> Option<Claim<'a>> {
+        let claim = slots.claim_first(self.start(slots), occupied)?;
+        if self.search == SearchStrategy::PerThread {
+            SEARCH_HINT.with(|hint| hint.set(claim.index));
+        }
+        Some(claim)
+    }
+
+    fn acquire_many<'a>(
+        &self,
+        slots: &'a SlotSet<'_>,
+        count: usize,
+        occupied: bool,
+        claims: &mut Vec<Claim<'a>>,
+    ) {
+        slots.claim_many(self.start(slots), count, occupied, claims);
+    }
+
+    fn release(&self, _: &SlotSet<'_>, _: usize) {}
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// A slot allocator which keeps lock-free stacks of the free slots, with separate stacks for slots which hold a value
+/// and those which are vacant. Pulls and returns take constant time, rather than searching the pool's bitset, which
+/// benefits pools with many thousands of elements. Slots are handed out in the reverse order of their return.
+///
+/// The stacks are linked through a shared array, so each slot may be listed at most once. A slot popped from a stack
+/// must still be claimed, and if it is in use, it is pushed again when it is next released.
+pub struct FreeListAllocator {
+    /// The top of the stack of slots which hold a value.
+    occupied: AtomicU64,
+    /// The top of the stack of vacant slots.
+    vacant: AtomicU64,
+    /// For each slot, one more than the index of the slot below it in its stack, or zero if it is at the bottom.
+    next: Box<[AtomicU32]>,
+    /// A bitset representing the slots which are listed in either stack.
+    listed: Bitset,
+}
+
+impl FreeListAllocator {
+    /// Creates an allocator for the given number of slots, which must be less than `u32::MAX`.
+    pub fn new(len: usize) -> Self {
+        assert!(
+            len < u32::MAX as usize,
+            "Pool was too large to use a free list."
+        );
+
+        Self {
+            occupied: AtomicU64::new(0),
+            vacant: AtomicU64::new(0),
+            next: (0..len).map(|_| AtomicU32::new(0)).collect(),
+            listed: Bitset::new(
+                len.saturating_sub(1) / usize::BITS as usize + 1,
+                false,
+                |_| 0,
+            ),
+        }
+    }
+
+    /// Lists the given slot on the stack of slots which hold a value, or of vacant ones if `occupied` is unset.
+    /// Nothing happens if the slot is already listed, or is being popped by another thread; in the latter case,
+    /// that thread will find the slot free and claim it.
+    fn push(&self, index: usize, occupied: bool) {
+        if !self.listed.insert(index, Ordering::SeqCst) {
+            return;
+        }
+
+        let head = self.head(occupied);
+        let mut current = head.load(Ordering::Relaxed);
+        loop {
+            self.next[index].store(current as u32, Ordering::Relaxed);
+            let new = Self::tagged(current, index as u64 + 1);
+            match head.compare_exchange_weak(current, new, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Removes the top slot from the stack of slots which hold a value, or of vacant ones if `occupied` is unset.
+    fn pop(&self, occupied: bool) -> Option<usize> {
+        let head = self.head(occupied);
+        let mut current = head.load(Ordering::Acquire);
+        loop {
+            let index = (current as u32).checked_sub(1)? as usize;
+            let new = Self::tagged(current, self.next[index].load(Ordering::Relaxed) as u64);
+            match head.compare_exchange_weak(current, new, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => {
+                    self.listed.remove(index, Ordering::SeqCst);
+                    return Some(index);
+                }
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Gets the top of the stack of slots which hold a value, or of vacant ones if `occupied` is unset.
+    fn head(&self, occupied: bool) -> &AtomicU64 {
+        if occupied {
+            &self.occupied
+        } else {
+            &self.vacant
+        }
+    }
+
+    /// Creates a new stack top which refers to the given entry. The top's upper half holds a tag which changes
+    /// with every update, so that a stale top is never mistaken for a current one.
+    fn tagged(current: u64, entry: u64) -> u64 {
+        ((current >> 32).wrapping_add(1) << 32) | entry
+    }
+}
+
+impl SlotAllocator for FreeListAllocator {
+    fn acquire<'a>(&self, slots: &'a SlotSet<'_>, occupied: bool) -> Option<Claim<'a>> {
+        loop {
+            let index = if occupied {
+                self.pop(true)?
+            } else {
+                self.pop(false).or_else(|| self.pop(true))?
+            };
+
+            // A slot on the wrong stack is moved, rather than claimed, since free slots never change their value.
+            if occupied && !slots.holds_value(index) {
+                self.push(index, false);
+            } else if let Some(claim) = slots.try_claim(index) {
+                return Some(claim);
+            }
+        }
+    }
+
+    fn release(&self, slots: &SlotSet<'_>, index: usize) {
+        self.push(index, slots.holds_value(index));
+    }
+
+    fn len(&self) -> usize {
+        self.next.len()
+    }
+}
+
+impl std::fmt::Debug for FreeListAllocator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FreeListAllocator")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Determines how an object is reset when it is returned to the pool.
+pub trait Reset<T> {
+    /// Resets the provided value.
+    fn reset(value: &mut T);
+
+    /// Resets the provided value, or returns an error describing how to handle a value which cannot be
+    /// reused. By default, this calls [`Reset::reset`] and accepts the value.
+    fn try_reset(value: &mut T) -> Result<(), ResetError> {
+        Self::reset(value);
+        Ok(())
+    }
+}
+
+/// Describes what should happen to a value which could not be reset for reuse.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ResetError {
+    /// The value is dropped, leaving its element vacant. The element is recreated by the pool's factory,
+    /// or by [`FixedPool::pull_or_create_async`], the next time that it is needed.
+    Discard,
+    /// The value is kept, but its element is poisoned and withheld from the pool until it is repaired
+    /// with [`FixedPool::clear_poison`].
+    Poison,
+}
+
+impl std::fmt::Display for ResetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Discard => f.write_str("value was discarded"),
+            Self::Poison => f.write_str("value was poisoned"),
+        }
+    }
+}
+
+impl std::error::Error for ResetError {}
+
+/// Does nothing when resetting an object.
+#[derive(Copy, Clone, Debug)]
+pub struct NoopReset;
+
+impl<T> Reset<T> for NoopReset {
+    fn reset(_: &mut T) {}
+}
+
+/// Determines whether an object may still be used when it is pulled from the pool.
+pub trait Validate<T> {
+    /// Whether the provided value may be handed out. If the pool has a factory, values which fail validation are
+    /// dropped and their elements are recreated. Otherwise, their elements are poisoned, so that they may be inspected
+    /// and repaired with [`FixedPool::clear_poison`], and the pull moves on to another element.
+    fn validate(value: &mut T) -> bool;
+}
+
+/// Accepts every object without checking it.
+#[derive(Copy, Clone, Debug)]
+pub struct NoopValidate;
+
+impl<T> Validate<T> for NoopValidate {
+    fn validate(_: &mut T) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts how many times a task has been woken.
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl CountingWaker {
+        /// The number of times that the task has been woken.
+        fn count(&self) -> usize {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_by_ref();
+        }
+
+        fn wake_by_ref(self: &Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Polls a future once with the provided waker.
+    fn poll_once<F: Future + Unpin>(future: &mut F, waker: &Arc<CountingWaker>) -> Poll<F::Output> {
+        let waker = Waker::from(waker.clone());
+        Pin::new(future).poll(&mut Context::from_waker(&waker))
+    }
+
+    /// Checks that a pending pull is woken and completed once an element is returned.
+    #[test]
+    fn pull_async_waits_for_return() {
+        let pool = FixedPool::<u32>::new([7]);
+        let borrow = pool.pull().unwrap();
+
+        let waker = Arc::default();
+        let mut future = pool.pull_async();
+        assert!(poll_once(&mut future, &waker).is_pending());
+        assert_eq!(waker.count(), 0);
+
+        drop(borrow);
+        assert_eq!(waker.count(), 1);
+        let Poll::Ready(borrow) = poll_once(&mut future, &waker) else {
+            panic!("woken future did not receive the element");
+        };
+        assert_eq!(*borrow, 7);
+        assert!(pool.pull().is_none());
+    }
+
+    /// Checks that a future which is dropped after being woken passes the wakeup on to the next waiter.
+    #[test]
+    fn dropped_future_passes_wakeup() {
+        let pool = FixedPool::<u32>::new([7]);
+        let borrow = pool.pull().unwrap();
+
+        let (waker_a, waker_b) = (Arc::default(), Arc::default());
+        let mut future_a = pool.pull_async();
+        let mut future_b = pool.pull_async();
+        assert!(poll_once(&mut future_a, &waker_a).is_pending());
+        assert!(poll_once(&mut future_b, &waker_b).is_pending());
+
+        drop(borrow);
+        assert_eq!(waker_a.count(), 1);
+        assert_eq!(waker_b.count(), 0);
+
+        drop(future_a);
+        assert_eq!(waker_b.count(), 1);
+        let Poll::Ready(borrow) = poll_once(&mut future_b, &waker_b) else {
+            panic!("woken future did not receive the element");
+        };
+        assert_eq!(*borrow, 7);
+    }
+
+    /// Checks that a timed pull gives up once its timeout elapses without an element being returned.
+    #[test]
+    fn pull_timeout_expires() {
+        let pool = FixedPool::<u32>::new([7]);
+        let _borrow = pool.pull().unwrap();
+
+        let start = Instant::now();
+        assert!(pool.pull_timeout(Duration::from_millis(20)).is_none());
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    /// Checks that blocked pulls are handed elements as they are returned by other threads.
+    #[test]
+    fn blocked_pulls_receive_returned_elements() {
+        let pool = FixedPool::<u32>::new([7]);
+        let borrow = pool.pull().unwrap();
+
+        std::thread::scope(|scope| {
+            let timed = scope.spawn(|| *pool.pull_timeout(Duration::from_secs(10)).unwrap());
+            let blocking = scope.spawn(|| *pool.pull_blocking());
+            std::thread::sleep(Duration::from_millis(20));
+            drop(borrow);
+            assert_eq!(timed.join().unwrap(), 7);
+            assert_eq!(blocking.join().unwrap(), 7);
+        });
+
+        assert!(pool.pull().is_some());
+    }
+
+    /// Checks that a fair pool grants a returned element to the waiting future, rather than a barging pull.
+    #[test]
+    fn fair_grant_beats_barging_pull() {
+        let pool = FixedPool::<u32>::builder().elements([7]).fair(true).build();
+        let borrow = pool.pull().unwrap();
+
+        let waker = Arc::default();
+        let mut future = pool.pull_async();
+        assert!(poll_once(&mut future, &waker).is_pending());
+
+        drop(borrow);
+        assert!(pool.pull().is_none());
+        let Poll::Ready(borrow) = poll_once(&mut future, &waker) else {
+            panic!("fair pool did not grant the element to the waiting future");
+        };
+        assert_eq!(*borrow, 7);
+        drop(borrow);
+        assert!(pool.pull().is_some());
+    }
+
+    /// Checks that an element granted to a future which is dropped before taking it passes to the next waiter.
+    #[test]
+    fn dropped_fair_future_passes_grant() {
+        let pool = FixedPool::<u32>::builder().elements([7]).fair(true).build();
+        let borrow = pool.pull().unwrap();
+
+        let (waker_a, waker_b) = (Arc::default(), Arc::default());
+        let mut future_a = pool.pull_async();
+        let mut future_b = pool.pull_async();
+        assert!(poll_once(&mut future_a, &waker_a).is_pending());
+        assert!(poll_once(&mut future_b, &waker_b).is_pending());
+
+        drop(borrow);
+        assert_eq!(waker_a.count(), 1);
+        assert_eq!(waker_b.count(), 0);
+
+        drop(future_a);
+        assert_eq!(waker_b.count(), 1);
+        assert!(pool.pull().is_none());
+        let Poll::Ready(borrow) = poll_once(&mut future_b, &waker_b) else {
+            panic!("granted element was not passed to the next waiter");
+        };
+        assert_eq!(*borrow, 7);
+    }
+
+    /// Checks that fair pools serve waiters in the order that they began waiting.
+    #[test]
+    fn fair_waiters_are_served_in_order() {
+        let pool = FixedPool::<u32>::builder().elements([7]).fair(true).build();
+        let borrow = pool.pull().unwrap();
+
+        let wakers = [(); 3].map(|_| Arc::default());
+        let mut futures = [(); 3].map(|_| pool.pull_async());
+        for (future, waker) in futures.iter_mut().zip(&wakers) {
+            assert!(poll_once(future, waker).is_pending());
+        }
+
+        let mut borrow = Some(borrow);
+        for i in 0..3 {
+            drop(borrow.take());
+            for j in i + 1..3 {
+                assert!(poll_once(&mut futures[j], &wakers[j]).is_pending());
+            }
+            let Poll::Ready(next) = poll_once(&mut futures[i], &wakers[i]) else {
+                panic!("waiter {i} was not served in order");
+            };
+            borrow = Some(next);
+        }
+    }
+
+    /// Checks that a fair waiter whose granted element fails validation keeps its place in the queue.
+    #[test]
+    fn fair_waiter_keeps_place_after_failed_validation() {
+        let pool = FixedPool::<u32, NoopReset, Even>::builder()
+            .elements([2, 4])
+            .fair(true)
+            .build();
+        let mut first = pool.pull().unwrap();
+        let second = pool.pull().unwrap();
+
+        let (waker_a, waker_b) = (Arc::default(), Arc::default());
+        let mut future_a = pool.pull_async();
+        let mut future_b = pool.pull_async();
+        assert!(poll_once(&mut future_a, &waker_a).is_pending());
+        assert!(poll_once(&mut future_b, &waker_b).is_pending());
+
+        *first = 1;
+        drop(first);
+        assert!(poll_once(&mut future_a, &waker_a).is_pending());
+        assert!(pool.is_poisoned(0));
+
+        drop(second);
+        assert!(poll_once(&mut future_b, &waker_b).is_pending());
+        let Poll::Ready(borrow) = poll_once(&mut future_a, &waker_a) else {
+            panic!("waiter lost its place after its element failed validation");
+        };
+        assert_eq!(*borrow, 4);
+    }
+
+    /// Checks that a pool creates new elements once its initial ones are in use, but never beyond its maximum size.
+    #[test]
+    fn grows_up_to_max_size() {
+        let created = AtomicUsize::new(0);
+        let pool = FixedPool::<u32>::builder()
+            .elements([1, 2])
+            .max_size(4)
+            .factory(move || 10 + created.fetch_add(1, Ordering::SeqCst) as u32)
+            .build();
+
+        let borrows = (0..4).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        assert!(pool.pull().is_none());
+        let mut values = borrows.iter().map(|x| **x).collect::<Vec<_>>();
+        values.sort_unstable();
+        assert_eq!(values, [1, 2, 10, 11]);
+
+        drop(borrows);
+        let borrows = (0..4).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        let mut values = borrows.iter().map(|x| **x).collect::<Vec<_>>();
+        values.sort_unstable();
+        assert_eq!(values, [1, 2, 10, 11], "returned elements should be reused");
+    }
+
+    /// Checks that a factory which panics leaves its element free to be created by a later pull.
+    #[test]
+    fn recovers_from_factory_panic() {
+        let fail = AtomicBool::new(true);
+        let pool = FixedPool::<u32>::builder()
+            .max_size(2)
+            .factory(move || {
+                assert!(!fail.swap(false, Ordering::SeqCst), "factory failed");
+                5
+            })
+            .build();
+
+        assert!(std::panic::catch_unwind(AssertUnwindSafe(|| pool.pull())).is_err());
+        let borrows = (0..2).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        assert!(borrows.iter().all(|x| **x == 5));
+        assert!(pool.pull().is_none());
+    }
+
+    /// Checks that idle elements are evicted down to the minimum size, while recently returned ones are kept.
+    #[test]
+    fn evict_idle_respects_min_size() {
+        let created = Arc::new(AtomicUsize::new(0));
+        let pool = FixedPool::<u32>::builder()
+            .elements(0..6)
+            .max_size(6)
+            .factory({
+                let created = created.clone();
+                move || {
+                    created.fetch_add(1, Ordering::SeqCst);
+                    9
+                }
+            })
+            .idle_timeout(Duration::from_millis(50))
+            .min_size(2)
+            .build();
+
+        let mut borrows = (0..6).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        let fresh = borrows.pop().unwrap();
+        drop(borrows);
+        std::thread::sleep(Duration::from_millis(60));
+        drop(fresh);
+
+        assert_eq!(pool.evict_idle(), 4);
+        assert_eq!(pool.evict_idle(), 0);
+
+        let borrows = (0..6).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        assert_eq!(created.load(Ordering::SeqCst), 4);
+        let mut values = borrows.iter().map(|x| **x).collect::<Vec<_>>();
+        values.sort_unstable();
+        assert_eq!(values, [0, 5, 9, 9, 9, 9]);
+    }
+
+    /// Checks that pools without a factory keep their idle elements, since pulls could not recreate them.
+    #[test]
+    fn evict_idle_requires_factory() {
+        let pool = FixedPool::<u32>::builder()
+            .elements(0..2)
+            .idle_timeout(Duration::ZERO)
+            .build();
+
+        assert_eq!(pool.evict_idle(), 0);
+        assert_eq!(pull_indices(&pool, 2).len(), 2);
+    }
+
+    /// Checks that an evictor thread exits promptly once its pool is dropped, rather than after its interval.
+    #[test]
+    fn evictor_exits_when_pool_dropped() {
+        let pool = FixedPool::<u32>::builder()
+            .elements(0..2)
+            .factory(|| 0)
+            .idle_timeout(Duration::ZERO)
+            .build();
+        let evictor = pool.spawn_evictor(Duration::from_secs(3600));
+
+        drop(pool);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !evictor.is_finished() {
+            assert!(Instant::now() < deadline, "evictor outlived its pool");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        evictor.join().unwrap();
+    }
+
+    /// Checks that elements are created when they are first pulled, and reused afterward.
+    #[test]
+    fn with_factory_creates_lazily() {
+        let created = Arc::new(AtomicUsize::new(0));
+        let pool = FixedPool::<usize>::with_factory(3, {
+            let created = created.clone();
+            move || created.fetch_add(1, Ordering::SeqCst)
+        });
+        assert_eq!(created.load(Ordering::SeqCst), 0);
+
+        let first = pool.pull().unwrap();
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        drop(first);
+        assert_eq!(*pool.pull().unwrap(), 0);
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+
+        let borrows = (0..3).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        assert_eq!(created.load(Ordering::SeqCst), 3);
+        assert!(pool.pull().is_none());
+        drop(borrows);
+    }
+
+    /// Rejects values of zero when they are returned, as a connection pool would reject a broken connection.
+    struct RejectZero;
+
+    impl Reset<u32> for RejectZero {
+        fn reset(_: &mut u32) {}
+
+        fn try_reset(value: &mut u32) -> Result<(), ResetError> {
+            if *value == 0 {
+                Err(ResetError::Discard)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks that rejected elements are recreated by the next pull which can create them, and that a failed
+    /// creation leaves the element vacant.
+    #[test]
+    fn rejected_elements_are_recreated() {
+        let pool = FixedPool::<u32, RejectZero>::builder().max_size(1).build();
+        let waker = Arc::default();
+        assert!(pool.pull().is_none());
+
+        let mut future = Box::pin(pool.pull_or_create_async(|| ready(Ok::<_, ()>(1))));
+        let Poll::Ready(Ok(mut borrow)) = poll_once(&mut future, &waker) else {
+            panic!("vacant element was not created");
+        };
+        drop(future);
+        assert_eq!(*borrow, 1);
+        *borrow = 0;
+        drop(borrow);
+        assert!(pool.pull().is_none());
+
+        let mut future = Box::pin(pool.pull_or_create_async(|| ready(Err(()))));
+        assert!(matches!(
+            poll_once(&mut future, &waker),
+            Poll::Ready(Err(()))
+        ));
+        drop(future);
+
+        let mut future = Box::pin(pool.pull_or_create_async(|| ready(Ok::<_, ()>(2))));
+        let Poll::Ready(Ok(borrow)) = poll_once(&mut future, &waker) else {
+            panic!("rejected element was not recreated");
+        };
+        assert_eq!(*borrow, 2);
+    }
+
+    /// Checks that idle elements are evicted from pools without a factory once they are pulled from in a way which
+    /// recreates elements.
+    #[test]
+    fn evicted_elements_are_recreated_asynchronously() {
+        let pool = FixedPool::<u32, RejectZero>::builder()
+            .max_size(1)
+            .idle_timeout(Duration::ZERO)
+            .build();
+        let waker = Arc::default();
+
+        let mut future = Box::pin(pool.pull_or_create_async(|| ready(Ok::<_, ()>(1))));
+        let Poll::Ready(Ok(borrow)) = poll_once(&mut future, &waker) else {
+            panic!("vacant element was not created");
+        };
+        drop((future, borrow));
+        assert_eq!(pool.evict_idle(), 1);
+        assert!(pool.pull().is_none());
+
+        let mut future = Box::pin(pool.pull_or_create_async(|| ready(Ok::<_, ()>(2))));
+        let Poll::Ready(Ok(borrow)) = poll_once(&mut future, &waker) else {
+            panic!("evicted element was not recreated");
+        };
+        assert_eq!(*borrow, 2);
+    }
+
+    /// Discards values of zero and poisons values of one when they are returned.
+    struct Triage;
+
+    impl Reset<u32> for Triage {
+        fn reset(_: &mut u32) {}
+
+        fn try_reset(value: &mut u32) -> Result<(), ResetError> {
+            match value {
+                0 => Err(ResetError::Discard),
+                1 => Err(ResetError::Poison),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    /// Checks that discarded elements are recreated by the factory, and poisoned ones are withheld from the pool.
+    #[test]
+    fn reset_discards_and_poisons() {
+        let pool = FixedPool::<u32, Triage>::builder()
+            .elements([5, 6])
+            .factory(|| 7)
+            .build();
+
+        let mut discarded = pool.pull().unwrap();
+        let mut poisoned = pool.pull().unwrap();
+        let (discarded_index, poisoned_index) = (discarded.index(), poisoned.index());
+        *discarded = 0;
+        *poisoned = 1;
+        drop((discarded, poisoned));
+
+        assert!(!pool.is_poisoned(discarded_index));
+        assert!(pool.is_poisoned(poisoned_index));
+        let borrow = pool.pull().unwrap();
+        assert_eq!((borrow.index(), *borrow), (discarded_index, 7));
+        assert!(pool.pull().is_none());
+    }
+
+    /// Accepts only even values when they are pulled.
+    struct Even;
+
+    impl Validate<u32> for Even {
+        fn validate(value: &mut u32) -> bool {
+            value.is_multiple_of(2)
+        }
+    }
+
+    /// Checks that a value which fails validation is replaced by the factory.
+    #[test]
+    fn validation_failure_recreates_element() {
+        let pool = FixedPool::<u32, NoopReset, Even>::builder()
+            .elements([1, 2])
+            .factory(|| 4)
+            .build();
+
+        let first = pool.pull().unwrap();
+        assert_eq!((first.index(), *first), (0, 4));
+        let second = pool.pull().unwrap();
+        assert_eq!((second.index(), *second), (1, 2));
+        assert!(pool.pull().is_none());
+    }
+
+    /// Checks that values which fail validation are poisoned in pools without a factory, so that they may be repaired.
+    #[test]
+    fn validation_failure_poisons_element() {
+        let pool = FixedPool::<u32, NoopReset, Even>::new([1, 2, 3]);
+
+        let borrow = pool.pull().unwrap();
+        assert_eq!((borrow.index(), *borrow), (1, 2));
+        assert!(pool.pull().is_none());
+        assert_eq!(pool.poisoned().collect::<Vec<_>>(), [0, 2]);
+
+        assert_eq!(pool.clear_poison(0, 4), Some(1));
+        assert_eq!(*pool.pull().unwrap(), 4);
+    }
+
+    /// Panics when resetting or validating a value of thirteen.
+    struct Unlucky;
+
+    impl Reset<u32> for Unlucky {
+        fn reset(value: &mut u32) {
+            assert_ne!(*value, 13, "unlucky value");
+        }
+    }
+
+    impl Validate<u32> for Unlucky {
+        fn validate(value: &mut u32) -> bool {
+            assert_ne!(*value, 13, "unlucky value");
+            true
+        }
+    }
+
+    /// Checks that an element whose reset panics is poisoned, and may be repaired with a new value.
+    #[test]
+    fn reset_panic_poisons_element() {
+        let pool = FixedPool::<u32, Unlucky>::new([1, 2]);
+
+        let mut borrow = pool.pull().unwrap();
+        let index = borrow.index();
+        *borrow = 13;
+        assert!(std::panic::catch_unwind(AssertUnwindSafe(|| drop(borrow))).is_err());
+        assert!(pool.is_poisoned(index));
+        assert_eq!(pool.poisoned().collect::<Vec<_>>(), [index]);
+
+        let other = pool.pull().unwrap();
+        assert_ne!(other.index(), index);
+        assert!(pool.pull().is_none());
+
+        assert_eq!(pool.clear_poison(index, 3), Some(13));
+        assert!(!pool.is_poisoned(index));
+        let repaired = pool.pull().unwrap();
+        assert_eq!((repaired.index(), *repaired), (index, 3));
+    }
+
+    /// Checks that an element whose validation panics is poisoned, without affecting the rest of the pool.
+    #[test]
+    fn validate_panic_poisons_element() {
+        let pool = FixedPool::<u32, NoopReset, Unlucky>::new([13, 2]);
+
+        assert!(std::panic::catch_unwind(AssertUnwindSafe(|| pool.pull())).is_err());
+        assert!(pool.is_poisoned(0));
+        assert_eq!(*pool.pull().unwrap(), 2);
+    }
+
+    /// Checks that the counts and occupancy map reflect borrowed and poisoned elements.
+    #[test]
+    fn counts_borrowed_and_poisoned_elements() {
+        let pool = FixedPool::<u32, Triage>::new([2, 3, 4, 5]);
+        assert_eq!(
+            (pool.capacity(), pool.available(), pool.in_use()),
+            (4, 4, 0)
+        );
+
+        let borrows = (0..2).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        let mut poisoned = pool.pull().unwrap();
+        *poisoned = 1;
+        drop(poisoned);
+
+        assert_eq!(
+            (pool.capacity(), pool.available(), pool.in_use()),
+            (4, 1, 2)
+        );
+        assert!(pool.is_borrowed(0) && pool.is_borrowed(1));
+        assert!(!pool.is_borrowed(2) && !pool.is_borrowed(3));
+        let debug = format!("{pool:?}");
+        assert!(
+            debug.contains("capacity: 4, available: 1, in_use: 2"),
+            "{debug}"
+        );
+        assert!(debug.contains(r###"occupancy: "##!.""###), "{debug}");
+
+        drop(borrows);
+        assert_eq!((pool.available(), pool.in_use()), (3, 0));
+    }
+
+    /// Checks that vacant elements only count as available when the pool has a factory to create them.
+    #[test]
+    fn vacant_elements_need_factory_to_be_available() {
+        let builder = || FixedPool::<u32>::builder().elements([1]).max_size(3);
+
+        let pool = builder().build();
+        assert_eq!((pool.available(), pool.in_use()), (1, 0));
+        let debug = format!("{pool:?}");
+        assert!(debug.contains(r#"occupancy: ".__""#), "{debug}");
+
+        let pool = builder().factory(|| 2).build();
+        assert_eq!((pool.available(), pool.in_use()), (3, 0));
+        let debug = format!("{pool:?}");
+        assert!(debug.contains(r#"occupancy: "...""#), "{debug}");
+    }
+
+    /// Checks that borrows and futures describe themselves without formatting their whole pool.
+    #[test]
+    fn debug_output_is_bounded() {
+        let pool = FixedPool::<u32>::new(0..1000);
+        let borrow = pool.pull().unwrap();
+        assert_eq!(format!("{borrow:?}"), "PoolBorrow { index: 0, value: 0 }");
+        let scoped = pool.pull_scoped().unwrap();
+        assert_eq!(format!("{scoped:?}"), "ScopedBorrow { index: 1, value: 1 }");
+        let future = pool.pull_async();
+        assert_eq!(
+            format!("{future:?}"),
+            "PullFuture { pool: None, waiter: None, vacant: false, .. }"
+        );
+
+        let debug = format!("{pool:?}");
+        let occupancy = format!(r###"occupancy: "##{}" and 744 more"###, ".".repeat(254));
+        assert!(debug.contains(&occupancy), "{debug}");
+    }
+
+    /// Checks that pulls, failures, peak borrows and hold times are recorded.
+    #[cfg(feature = "stats")]
+    #[test]
+    fn stats_record_pulls_and_hold_times() {
+        let pool = FixedPool::<u32>::new([1, 2]);
+        let first = pool.pull().unwrap();
+        let second = pool.pull().unwrap();
+        assert!(pool.pull().is_none());
+        drop(first);
+        std::thread::sleep(Duration::from_millis(5));
+        drop(second);
+        let _third = pool.pull().unwrap();
+
+        let stats = pool.stats();
+        assert_eq!((stats.pulls, stats.failed_pulls), (3, 1));
+        assert_eq!((stats.borrowed, stats.peak_borrowed), (1, 2));
+        assert_eq!(stats.hold_times.count(), 2);
+        assert!(stats.hold_times.quantile(1.0).unwrap() > Duration::from_millis(5));
+    }
+
+    /// Describes the registered pools with the given name.
+    fn registered_named(name: &str) -> Vec<PoolInfo> {
+        registered_pools()
+            .into_iter()
+            .filter(|x| x.name.as_deref() == Some(name))
+            .collect()
+    }
+
+    /// Checks that registered pools are listed until their last handle is dropped.
+    #[test]
+    fn registry_lists_live_pools() {
+        let pool = FixedPool::<u32>::builder()
+            .elements([1, 2])
+            .name("registry_lists_live_pools")
+            .build();
+        let unregistered = FixedPool::<u32>::builder()
+            .elements([1])
+            .name("registry_lists_live_pools")
+            .build();
+        assert!(registered_named("registry_lists_live_pools").is_empty());
+
+        pool.register();
+        pool.register();
+        let borrow = pool.pull().unwrap();
+        let info = registered_named("registry_lists_live_pools");
+        assert_eq!(info.len(), 1);
+        assert_eq!(
+            (info[0].capacity, info[0].available, info[0].in_use),
+            (2, 1, 1)
+        );
+
+        let handle = pool.clone();
+        drop((pool, borrow, unregistered));
+        assert_eq!(registered_named("registry_lists_live_pools").len(), 1);
+        drop(handle);
+        assert!(registered_named("registry_lists_live_pools").is_empty());
+    }
+
+    /// Checks that borrows held past the threshold are reported along with where they were pulled.
+    #[cfg(feature = "leak-detection")]
+    #[test]
+    fn outstanding_borrows_report_pull_site() {
+        let pool = FixedPool::<u32>::new([1, 2, 3]);
+        let (line, old) = (line!(), pool.pull().unwrap());
+        let returned = pool.pull().unwrap();
+        drop(returned);
+        std::thread::sleep(Duration::from_millis(20));
+        let _recent = pool.pull().unwrap();
+
+        let outstanding = pool.outstanding_borrows(Duration::from_millis(10));
+        assert_eq!(outstanding.len(), 1);
+        assert_eq!(outstanding[0].index, old.index());
+        assert!(outstanding[0].held_for >= Duration::from_millis(20));
+        assert_eq!(outstanding[0].location.file(), file!());
+        assert_eq!(outstanding[0].location.line(), line);
+        assert_eq!(pool.outstanding_borrows(Duration::ZERO).len(), 2);
+    }
+
+    /// Pulls the given number of elements and returns their indices in the order that they were taken.
+    fn pull_indices<T, R: Reset<T>, V: Validate<T>>(
+        pool: &FixedPool<T, R, V>,
+        count: usize,
+    ) -> Vec<usize> {
+        let borrows = (0..count).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        borrows.iter().map(|x| x.index()).collect()
+    }
+
+    /// Checks where each search strategy begins looking for a free element.
+    #[test]
+    fn search_strategies_choose_start() {
+        let builder = || FixedPool::<u32>::builder().elements(0..192);
+
+        let pool = builder().search(SearchStrategy::LowestIndex).build();
+        assert_eq!(pull_indices(&pool, 4), [0, 1, 2, 3]);
+
+        let pool = builder().search(SearchStrategy::RoundRobin).build();
+        assert_eq!(pull_indices(&pool, 4), [0, 64, 128, 1]);
+
+        let pool = builder().search(SearchStrategy::PerThread).build();
+        let indices = pull_indices(&pool, 3);
+        assert_eq!(indices[0] % 64, 0);
+        assert_eq!(indices[1..], [indices[0] + 1, indices[0] + 2]);
+    }
+
+    /// Counts how many values of this type have been dropped.
+    struct Tracked(Arc<AtomicUsize>);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Checks that elements cached by a thread are returned to the pool when the thread exits.
+    #[test]
+    fn thread_cache_released_on_exit() {
+        let pool = FixedPool::<u32>::builder()
+            .elements(0..4)
+            .thread_cache(4)
+            .build();
+
+        // Unlike a scoped thread, joining a spawned thread waits for its thread-local storage to be destroyed.
+        let thread = {
+            let pool = pool.clone();
+            std::thread::spawn(move || {
+                let borrows = (0..4).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+                let last = borrows[3].index();
+                drop(borrows);
+                assert_eq!(pool.pull().unwrap().index(), last);
+            })
+        };
+        thread.join().unwrap();
+
+        assert_eq!(pool.available(), 4);
+        assert_eq!(pool.in_use(), 0);
+        let borrows = (0..4).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        assert!((0..4).all(|index| pool.is_borrowed(index)));
+        drop(borrows);
+    }
+
+    /// Checks that a thread which still caches elements of a dropped pool neither leaks nor double-drops them.
+    #[test]
+    fn thread_cache_outlives_pool() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let pool = FixedPool::<Tracked>::builder()
+            .elements((0..4).map(|_| Tracked(dropped.clone())))
+            .thread_cache(4)
+            .build();
+
+        let (cached_send, cached_recv) = mpsc::channel();
+        let (dropped_send, dropped_recv) = mpsc::channel::<()>();
+        let thread = {
+            let pool = pool.clone();
+            std::thread::spawn(move || {
+                drop(pool.pull().unwrap());
+                drop(pool);
+                cached_send.send(()).unwrap();
+                dropped_recv.recv().unwrap();
+            })
+        };
+
+        cached_recv.recv().unwrap();
+        drop(pool);
+        assert_eq!(dropped.load(Ordering::SeqCst), 4);
+        dropped_send.send(()).unwrap();
+        thread.join().unwrap();
+        assert_eq!(dropped.load(Ordering::SeqCst), 4);
+    }
+
+    /// Checks that other threads take elements from a thread's cache once nothing else is free.
+    #[test]
+    fn cached_elements_are_stolen() {
+        let pool = FixedPool::<u32>::builder()
+            .elements(0..4)
+            .thread_cache(4)
+            .build();
+
+        let barrier = Barrier::new(2);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                drop((0..4).map(|_| pool.pull().unwrap()).collect::<Vec<_>>());
+                barrier.wait();
+                barrier.wait();
+            });
+
+            barrier.wait();
+            let borrows = (0..4).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+            assert!(pool.pull().is_none());
+            drop(borrows);
+            barrier.wait();
+        });
+
+        assert_eq!(pool.available(), 4);
+    }
+
+    /// Checks that the LIFO strategy hands out the most recently returned elements first.
+    #[test]
+    fn lifo_reuses_recent_returns() {
+        let pool = FixedPool::<u32>::builder()
+            .elements(0..4)
+            .search(SearchStrategy::Lifo)
+            .build();
+
+        let mut borrows = (0..4).map(|_| pool.pull()).collect::<Vec<_>>();
+        assert!(borrows.iter().all(Option::is_some));
+        for index in [2, 0, 3] {
+            borrows[index] = None;
+        }
+
+        assert_eq!(pull_indices(&pool, 3), [3, 0, 2]);
+    }
+
+    /// Checks that elements released by a failed batch pull are not treated as recently returned.
+    #[test]
+    fn lifo_ignores_rolled_back_elements() {
+        let pool = FixedPool::<u32>::builder()
+            .elements(0..4)
+            .search(SearchStrategy::Lifo)
+            .build();
+
+        let first = pool.pull().unwrap();
+        let held = pool.pull().unwrap();
+        drop(first);
+        assert!(pool.pull_many(4).is_none());
+        assert_eq!(pool.pull().unwrap().index(), 0);
+        drop(held);
+    }
+
+    /// Checks that the free list never hands the same element to two threads at once, and loses none of them.
+    #[test]
+    fn free_list_contention() {
+        const CAPACITY: usize = 16;
+
+        let pool = FixedPool::<usize>::builder()
+            .elements(0..CAPACITY)
+            .allocator(FreeListAllocator::new(CAPACITY))
+            .build();
+        let held = (0..CAPACITY)
+            .map(|_| AtomicBool::new(false))
+            .collect::<Vec<_>>();
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..20000 {
+                        if let Some(borrow) = pool.pull() {
+                            assert_eq!(*borrow, borrow.index());
+                            assert!(!held[borrow.index()].swap(true, Ordering::SeqCst));
+                            std::hint::spin_loop();
+                            held[borrow.index()].store(false, Ordering::SeqCst);
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(pool.available(), CAPACITY);
+        let borrows = (0..CAPACITY)
+            .map(|_| pool.pull().unwrap())
+            .collect::<Vec<_>>();
+        assert!(pool.pull().is_none());
+        let mut indices = borrows.iter().map(PoolBorrow::index).collect::<Vec<_>>();
+        indices.sort_unstable();
+        assert_eq!(indices, (0..CAPACITY).collect::<Vec<_>>());
+    }
+
+    /// Checks that the free list hands out the most recently returned elements first, and creates vacant ones last.
+    #[test]
+    fn free_list_reuses_recent_returns() {
+        let pool = FixedPool::<usize>::builder()
+            .elements(0..4)
+            .max_size(6)
+            .factory(|| 9)
+            .allocator(FreeListAllocator::new(6))
+            .build();
+
+        let mut borrows = (0..4).map(|_| pool.pull()).collect::<Vec<_>>();
+        drop(borrows[2].take());
+        drop(borrows[0].take());
+        borrows[0] = pool.pull();
+        borrows[2] = pool.pull();
+        assert_eq!(borrows[0].as_ref().unwrap().index(), 0);
+        assert_eq!(borrows[2].as_ref().unwrap().index(), 2);
+
+        let created = pool.pull().unwrap();
+        assert_eq!((created.index(), *created), (4, 9));
+    }
+
+    /// Checks that a pool large enough to summarize its bitset finds elements returned to full words.
+    #[test]
+    fn large_pool_refills_after_exhaustion() {
+        const CAPACITY: usize = 4096 + 10;
+
+        let pool = FixedPool::<usize>::new(0..CAPACITY);
+        let mut borrows = (0..CAPACITY).map(|_| pool.pull()).collect::<Vec<_>>();
+        assert!(pool.pull().is_none());
+
+        for index in [3000, 17, CAPACITY - 1] {
+            drop(borrows[index].take());
+            let borrow = pool.pull().unwrap();
+            assert_eq!((borrow.index(), *borrow), (index, index));
+            assert!(pool.pull().is_none());
+            borrows[index] = Some(borrow);
+        }
+
+        drop(borrows);
+        assert_eq!(pool.available(), CAPACITY);
+        let borrows = (0..CAPACITY)
+            .map(|_| pool.pull().unwrap())
+            .collect::<Vec<_>>();
+        assert!(pool.pull().is_none());
+        drop(borrows);
+    }
+
+    /// Checks that a waiting pull finds an element in a word which the summary still records as full.
+    #[test]
+    fn waiting_pull_ignores_stale_summary() {
+        const CAPACITY: usize = 4096;
+
+        let pool = FixedPool::<usize>::new(0..CAPACITY);
+        let mut borrows = (0..CAPACITY).map(|_| pool.pull()).collect::<Vec<_>>();
+        drop(borrows[100].take());
+
+        // Recreate the moment at which a pull has filled the word and is about to mark it as full.
+        pool.0.pulled_elements.summary[0].fetch_or(1 << 1, Ordering::SeqCst);
+        assert!(pool.pull().is_none());
+
+        let borrow = pool.pull_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(borrow.index(), 100);
+    }
+
+    /// An allocator which hands out the free element with the highest index.
+    struct HighestFirst(usize);
+
+    impl SlotAllocator for HighestFirst {
+        fn acquire<'a>(&self, slots: &'a SlotSet<'_>, occupied: bool) -> Option<Claim<'a>> {
+            (0..slots.len())
+                .rev()
+                .filter(|&index| slots.is_free(index) && (!occupied || slots.holds_value(index)))
+                .find_map(|index| slots.try_claim(index))
+        }
+
+        fn release(&self, _: &SlotSet<'_>, _: usize) {}
+
+        fn len(&self) -> usize {
+            self.0
+        }
+    }
+
+    /// Checks that pools take elements in the order chosen by a custom allocator.
+    #[test]
+    fn custom_allocator_chooses_elements() {
+        let pool = FixedPool::<usize>::builder()
+            .elements(0..4)
+            .max_size(6)
+            .factory(|| 9)
+            .allocator(HighestFirst(6))
+            .build();
+
+        let borrows = (0..6).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        let pulled = borrows.iter().map(|x| (x.index(), **x)).collect::<Vec<_>>();
+        assert_eq!(pulled, [(3, 3), (2, 2), (1, 1), (0, 0), (5, 9), (4, 9)]);
+        assert!(pool.pull().is_none());
+
+        drop(borrows);
+        assert_eq!(pull_indices(&pool, 2), [5, 4]);
+    }
+
+    /// Checks that a sharded pool takes elements from other shards once the local one is exhausted, and that
+    /// borrows return to the shard they came from.
+    #[test]
+    fn sharded_pool_steals_from_other_shards() {
+        let pool = ShardedPool::<usize>::new(2, 0..4);
+        let local = pool.local_shard();
+
+        let mut borrows = (0..4).map(|_| pool.pull().unwrap()).collect::<Vec<_>>();
+        let shards = borrows.iter().map(|x| **x % 2).collect::<Vec<_>>();
+        assert_eq!(shards, [local, local, 1 - local, 1 - local]);
+        assert!(pool.pull().is_none());
+        assert_eq!((pool.available(), pool.in_use()), (0, 4));
+
+        drop(borrows.remove(2));
+        assert_eq!(pool.shards()[1 - local].available(), 1);
+        assert_eq!(*pool.pull_scoped().unwrap() % 2, 1 - local);
+        assert_eq!(pool.shards()[local].in_use(), 2);
+    }
+
+    /// Checks that batch pulls take every requested element or none of them.
+    #[test]
+    fn pull_many_is_all_or_nothing() {
+        let pool = FixedPool::<u32>::new(0..4);
+        let held = pool.pull_scoped().unwrap();
+        let other = pool.pull_scoped().unwrap();
+
+        assert!(pool.pull_many(3).is_none());
+        assert_eq!((pool.available(), pool.in_use()), (2, 2));
+
+        let batch = pool.pull_many(2).unwrap();
+        assert_eq!(batch.iter().map(|x| **x).collect::<Vec<_>>(), [2, 3]);
+        assert!(pool.pull().is_none());
+
+        drop((held, other, batch));
+        assert_eq!(pool.pull_many(4).unwrap().len(), 4);
+        assert_eq!(pool.available(), 4);
+    }
+
+    /// Checks that batch pulls create vacant elements when the pool has a factory.
+    #[test]
+    fn pull_many_creates_vacant_elements() {
+        let pool = FixedPool::<u32>::builder()
+            .elements(0..2)
+            .max_size(4)
+            .factory(|| 9)
+            .build();
+
+        let batch = pool.pull_many(4).unwrap();
+        let mut values = batch.iter().map(|x| **x).collect::<Vec<_>>();
+        values.sort_unstable();
+        assert_eq!(values, [0, 1, 9, 9]);
+    }
+
+    /// Checks that a batch pull which fails validation partway through returns the elements it had taken.
+    #[test]
+    fn pull_many_rolls_back_on_poisoned_element() {
+        let pool = FixedPool::<u32, NoopReset, Unlucky>::new([2, 13, 4]);
+
+        assert!(std::panic::catch_unwind(AssertUnwindSafe(|| pool.pull_many(3))).is_err());
+        assert!(pool.is_poisoned(1));
+        assert_eq!((pool.available(), pool.in_use()), (2, 0));
+
+        let batch = pool.pull_many(2).unwrap();
+        assert_eq!(batch.iter().map(|x| **x).collect::<Vec<_>>(), [2, 4]);
+        assert!(pool.pull_many(1).is_none());
+    }
+
+    /// Checks that batch pulls replace values which fail validation with other elements.
+    #[test]
+    fn pull_many_skips_invalid_elements() {
+        let pool = FixedPool::<u32, NoopReset, Even>::new([2, 1, 4, 6]);
+
+        let batch = pool.pull_many(3).unwrap();
+        let mut values = batch.iter().map(|x| **x).collect::<Vec<_>>();
+        values.sort_unstable();
+        assert_eq!(values, [2, 4, 6]);
+        assert!(pool.pull_many(1).is_none());
+        assert!(pool.is_poisoned(1));
+    }
+
+    /// Checks that batches larger than the pool are refused without being attempted.
+    #[test]
+    fn pull_many_rejects_oversized_batches() {
+        let pool = FixedPool::<u32>::new(0..4);
+        assert!(pool.pull_many(5).is_none());
+        assert!(pool.pull_many(usize::MAX).is_none());
+        assert_eq!(pool.available(), 4);
+    }
 }